@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use iprs::ipld::cbor::decode_roundtrip;
+
+// Decoder and encoder must not panic on arbitrary input, and what ever
+// decodes must re-encode into a canonical form that round-trips as is.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(out) = decode_roundtrip(data) {
+        assert_eq!(decode_roundtrip(&out).unwrap(), out);
+    }
+});
@@ -0,0 +1,27 @@
+//! Check that the IPLD data-model in iprs builds without `std`. Build
+//! this crate for a target without std, like
+//! `cargo build --target thumbv7em-none-eabihf`, or run its tests on the
+//! host with `cargo test`.
+
+#![cfg_attr(not(test), no_std)]
+
+use core::iter;
+
+use iprs::ipld::kind::{Basic, Key, Node};
+
+/// Construct `{"list": [10, 20, 30]}` and index into it, return the last
+/// item in the list.
+pub fn construct_and_index() -> Option<i128> {
+    let list: Basic = [10, 20, 30]
+        .iter()
+        .map(|x: &i128| Basic::from(*x))
+        .collect();
+    let doc: Basic = iter::once((Key::Text("list".into()), list)).collect();
+
+    let list = Node::get(&doc, &Key::Text("list".into())).ok()?;
+    list.get(&Key::Offset(2)).ok()?.to_integer()
+}
+
+#[cfg(test)]
+#[path = "lib_test.rs"]
+mod lib_test;
@@ -0,0 +1,6 @@
+use super::*;
+
+#[test]
+fn test_construct_and_index() {
+    assert_eq!(construct_and_index(), Some(30));
+}
@@ -2,16 +2,18 @@
 //!
 //! [cid]: https://github.com/multiformats/cid
 
-use bs58;
 use multibase::Base;
 
-use std::{fmt, result, str::FromStr};
+use core::{cmp, fmt, hash, result, str::FromStr};
 
+#[cfg(not(feature = "std"))]
+use crate::no_std_prelude::*;
+#[cfg(feature = "std")]
+use crate::peer_id::PeerId;
 use crate::{
     multibase::Multibase,
     multicodec::{self, Multicodec},
     multihash::Multihash,
-    peer_id::PeerId,
     Error, Result,
 };
 
@@ -30,7 +32,12 @@ pub enum Version {
 }
 
 /// Content Identifier.
-#[derive(Clone, Eq, PartialEq)]
+///
+/// Equality, ordering and hashing are based on the fields of the binary
+/// form of the CID, its version, content-type and multihash, so that two
+/// CIDs that differ only in their multibase are treated as same. None of
+/// them encode the CID.
+#[derive(Clone)]
 pub enum Cid {
     /// Cid version ZERO. Actually this is legacy.
     /// In the distant future, we may remove this support after sha2 breaks.
@@ -58,6 +65,44 @@ impl fmt::Display for Cid {
     }
 }
 
+impl Eq for Cid {}
+
+impl PartialEq for Cid {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_cmp_key() == other.to_cmp_key()
+    }
+}
+
+impl PartialOrd for Cid {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cid {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.to_cmp_key().cmp(&other.to_cmp_key())
+    }
+}
+
+impl hash::Hash for Cid {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.to_cmp_key().hash(state)
+    }
+}
+
+impl Cid {
+    // fields that make up the binary form, that is, version, content-type
+    // and multihash, leaving out the multibase. Borrowed from the CID, so
+    // that comparing and hashing do not allocate.
+    fn to_cmp_key(&self) -> (u8, u128, Option<(u128, &[u8])>) {
+        match self {
+            Cid::Zero(mh) => (0, multicodec::DAG_PB, mh.as_parts()),
+            Cid::One(_, codec, mh) => (1, codec.to_code(), mh.as_parts()),
+        }
+    }
+}
+
 impl fmt::Debug for Cid {
     fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
         <Cid as fmt::Display>::fmt(self, f)
@@ -72,6 +117,63 @@ impl FromStr for Cid {
     }
 }
 
+/// Serialize as multibase text for human readable formats, like JSON, and
+/// as binary CID for compact formats.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Cid {
+    fn serialize<S>(&self, s: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::Error as _;
+
+        if s.is_human_readable() {
+            s.serialize_str(&self.to_text(None).map_err(S::Error::custom)?)
+        } else {
+            s.serialize_bytes(&self.encode().map_err(S::Error::custom)?)
+        }
+    }
+}
+
+/// Counterpart of Serialize, multibase text for human readable formats,
+/// and binary CID for compact formats.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Cid {
+    fn deserialize<D>(d: D) -> result::Result<Cid, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        if d.is_human_readable() {
+            d.deserialize_str(CidVisitor)
+        } else {
+            d.deserialize_bytes(CidVisitor)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+struct CidVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for CidVisitor {
+    type Value = Cid;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cid, as multibase text or in binary form")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, text: &str) -> result::Result<Cid, E> {
+        Cid::from_text(text).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, data: &[u8]) -> result::Result<Cid, E> {
+        match Cid::decode(data).map_err(E::custom)? {
+            (cid, rem) if rem.is_empty() => Ok(cid),
+            (_, rem) => Err(E::custom(format!("{} trailing bytes after cid", rem.len()))),
+        }
+    }
+}
+
 impl Cid {
     /// Create a new Cid in Version-0 format from `data`. Here data
     /// shall be encoded in Multihash specification using SHA2-256
@@ -105,7 +207,19 @@ impl Cid {
     /// [CIDv1]: https://github.com/multiformats/cid#how-does-it-work
     ///
     pub fn new_v1(base: Base, content_type: Multicodec, data: &[u8]) -> Result<Cid> {
-        let mh = Multihash::new(multicodec::SHA2_256.into(), data)?;
+        Cid::new_v1_with_hash(base, content_type, multicodec::SHA2_256.into(), data)
+    }
+
+    /// Same as [Cid::new_v1], but the multihash is computed from `data`
+    /// using the algorithm `hash`, like sha2-512 or blake2b-256. Return
+    /// error if `hash` is not a supported multihash algorithm.
+    pub fn new_v1_with_hash(
+        base: Base,
+        content_type: Multicodec,
+        hash: Multicodec,
+        data: &[u8],
+    ) -> Result<Cid> {
+        let mh = Multihash::new(hash, data)?;
         Ok(Cid::One(base, content_type, mh))
     }
 
@@ -129,28 +243,59 @@ impl Cid {
         Cid::One(base, content_type, mh)
     }
 
+    /// Create a Cid-v1 from its parts, multicodec-content-type `codec` and
+    /// encoded multihash `mh`. Return error if `mh` is not exactly one
+    /// well formed multihash. Base32Lower base-encoding is implied.
+    ///
+    /// Named so, since [Cid::new_v1] computes the multihash from data.
+    pub fn from_parts_v1(codec: u64, mh: Vec<u8>) -> Result<Cid> {
+        let content_type = Multicodec::from_code(codec.into())?;
+        Ok(Cid::One(
+            Base::Base32Lower,
+            content_type,
+            decode_multihash(&mh)?,
+        ))
+    }
+
+    /// Create a Cid-v0 from encoded multihash `mh`. CIDv0 allows only
+    /// 32-byte SHA2-256 multihash, dag-pb content is implied.
+    ///
+    /// Named so, since [Cid::new_v0] computes the multihash from data.
+    pub fn from_parts_v0(mh: Vec<u8>) -> Result<Cid> {
+        let mh = decode_multihash(&mh)?;
+        let (codec, n) = (mh.to_codec()?, mh.to_digest()?.len());
+        if codec == multicodec::SHA2_256.into() && n == 32 {
+            Ok(Cid::Zero(mh))
+        } else {
+            err_at!(BadInput, msg: "cidv0 needs 32-byte sha2-256, got {} of {} bytes", codec, n)
+        }
+    }
+
     /// Create a Cid-v0 from peer-id.
+    #[cfg(feature = "std")]
     pub fn from_peer_id_v0(peer_id: PeerId) -> Self {
         Cid::Zero(peer_id.into())
     }
 
     /// Create a Cid-v1 from peer-id. _codec_ value is implied as
     /// _LIBP2P_KEY_.
+    #[cfg(feature = "std")]
     pub fn from_peer_id_v1(base: Base, peer_id: PeerId) -> Self {
         let code = multicodec::LIBP2P_KEY;
         Cid::One(base, code.into(), peer_id.into())
     }
 
     /// Decode a base encoded CID, human readable text. CID format can
-    /// either be in legacy (v0) format or CIDv1 format.
+    /// either be in legacy (v0) format or CIDv1 format. Fails if `text`
+    /// carries anything after the CID, use [Cid::split_path] to parse
+    /// CID prefixed paths.
     pub fn from_text(text: &str) -> Result<Cid> {
         let mut chars = text.chars();
         let cid = match (chars.next(), chars.next()) {
             (Some('Q'), Some('m')) | (Some('1'), Some(_)) if text.len() == 46 => {
                 // legacy format v0.
-                let bytes = err_at!(ParseError, bs58::decode(text.as_bytes()).into_vec())?;
-                let (mh, _) = Multihash::decode(&bytes)?;
-                Cid::Zero(mh)
+                let bytes = err_at!(ParseError, Base::Base58Btc.decode(text))?;
+                Cid::Zero(decode_multihash(&bytes)?)
             }
             (Some('Q'), Some('m')) | (Some('1'), Some(_)) => err_at!(ParseError, msg: "{}", text)?,
             _ => {
@@ -169,14 +314,24 @@ impl Cid {
                 }
 
                 let (content_type, bytes) = Multicodec::decode(bytes)?;
-                let (mh, _) = Multihash::decode(bytes)?;
-                Cid::One(base, content_type, mh)
+                Cid::One(base, content_type, decode_multihash(bytes)?)
             }
         };
 
         Ok(cid)
     }
 
+    /// Parse a CID prefixed path, like `<cid>/a/b`, return the CID along
+    /// with the remaining path, `a/b`, without the leading separator. The
+    /// remaining path is empty if `text` is only a CID.
+    pub fn split_path(text: &str) -> Result<(Cid, &str)> {
+        let (cid, path) = match text.find('/') {
+            Some(off) => (&text[..off], &text[off + 1..]),
+            None => (text, ""),
+        };
+        Ok((Cid::from_text(cid)?, path))
+    }
+
     /// Encode in base format. Use the supplied `base`, if none, fall back
     /// to default base used while constructing the Cid.
     ///
@@ -184,7 +339,7 @@ impl Cid {
     /// * If value is a CIDv1 variant, encoded using specified base format.
     pub fn to_text(&self, base: Option<Base>) -> Result<String> {
         let text = match self {
-            Cid::Zero(mh) => bs58::encode(mh.encode()?).into_string(),
+            Cid::Zero(mh) => Base::Base58Btc.encode(mh.encode()?),
             Cid::One(fallback_base, content_type, mh) => {
                 let mut data = {
                     let codec = Multicodec::from_code(multicodec::CID_V1)?;
@@ -289,8 +444,53 @@ impl Cid {
         }
     }
 
+    /// Validate this CID, its multihash framing must be consistent, that
+    /// is, it encodes into exactly one multihash whose declared length
+    /// matches the digest. And for recognized hash algorithms the digest
+    /// length must match the algorithm's output size, unknown algorithms
+    /// and identity hash skip the length check. CIDv0 must carry a
+    /// 32-byte SHA2-256 digest.
+    pub fn validate(&self) -> Result<()> {
+        let mh = decode_multihash(&self.to_multihash().encode()?)?;
+        let (codec, n) = (mh.to_codec()?, mh.to_digest()?.len());
+
+        if let Cid::Zero(_) = self {
+            if codec != multicodec::SHA2_256.into() {
+                err_at!(BadInput, msg: "cidv0 with {} multihash", codec)?
+            }
+        }
+
+        match codec.to_code() {
+            // identity and blake3 digests can be of any length.
+            multicodec::IDENTITY | multicodec::BLAKE3 => Ok(()),
+            _ => match Multihash::new(codec, &[]) {
+                Ok(refv) if refv.to_digest()?.len() != n => {
+                    let m = refv.to_digest()?.len();
+                    err_at!(BadInput, msg: "{} digest of {} bytes, expected {}", codec, n, m)
+                }
+                Ok(_) => Ok(()),
+                // unknown hash algorithm, only framing is validated.
+                Err(_) => Ok(()),
+            },
+        }
+    }
+
+    /// Return the prefix of this CID, that is, everything except the hash
+    /// digest. Refer [CidPrefix].
+    pub fn prefix(&self) -> Result<CidPrefix> {
+        let mh = self.to_multihash();
+        let prefix = CidPrefix {
+            version: self.to_version(),
+            codec: self.to_content_type(),
+            hash_code: mh.to_codec()?,
+            hash_len: mh.to_digest()?.len(),
+        };
+        Ok(prefix)
+    }
+
     /// If CID is pointing to a peer-id, that is if the content_type is
     /// _LIBP2P_KEY_, return the PeerId value.
+    #[cfg(feature = "std")]
     pub fn to_peer_id(&self) -> Option<PeerId> {
         let code = multicodec::LIBP2P_KEY;
         match self {
@@ -303,6 +503,35 @@ impl Cid {
     }
 }
 
+/// Shape of a CID, its version, content type, hash algorithm and digest
+/// length, without the digest itself. Use this to check whether a CID
+/// has the expected shape, refer [CidPrefix::matches].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct CidPrefix {
+    pub version: Version,
+    pub codec: Multicodec,
+    pub hash_code: Multicodec,
+    pub hash_len: usize,
+}
+
+impl CidPrefix {
+    /// Return whether `cid` has the same prefix.
+    pub fn matches(&self, cid: &Cid) -> bool {
+        match cid.prefix() {
+            Ok(prefix) => &prefix == self,
+            Err(_) => false,
+        }
+    }
+}
+
+// decode `data` as exactly one multihash, without trailing bytes.
+fn decode_multihash(data: &[u8]) -> Result<Multihash> {
+    match Multihash::decode(data)? {
+        (mh, rem) if rem.is_empty() => Ok(mh),
+        (_, rem) => err_at!(BadInput, msg: "{} trailing bytes after multihash", rem.len()),
+    }
+}
+
 #[cfg(test)]
 #[path = "cid_test.rs"]
 mod cid_test;
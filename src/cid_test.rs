@@ -2,6 +2,9 @@ use super::*;
 
 use digest::Digest;
 
+use std::collections::{hash_map::DefaultHasher, HashSet};
+use std::hash::{Hash, Hasher};
+
 #[test]
 fn test_cid_v1() {
     let data = b"beep boop";
@@ -67,6 +70,27 @@ fn test_cid_v0_str() {
     assert!(bad.is_err())
 }
 
+#[test]
+fn test_cid_split_path() {
+    let s = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+    let (cid, path) = Cid::split_path(&format!("{}/a/b", s)).unwrap();
+    assert_eq!(cid, Cid::from_text(s).unwrap());
+    assert_eq!(path, "a/b");
+
+    let (_, path) = Cid::split_path(s).unwrap();
+    assert_eq!(path, "");
+
+    let cid = Cid::new_v1(Base::Base32Lower, multicodec::DAG_CBOR.into(), b"path").unwrap();
+    let text = cid.to_text(None).unwrap();
+    let (val, path) = Cid::split_path(&format!("{}/x", text)).unwrap();
+    assert_eq!((val, path), (cid, "x"));
+
+    // trailing content is rejected, path or otherwise.
+    assert!(format!("{}/a/b", s).parse::<Cid>().is_err());
+    assert!(format!("{}aa", text).parse::<Cid>().is_err());
+    assert!(Cid::split_path("/a/b").is_err());
+}
+
 #[test]
 fn test_cid_v0_error() {
     let cid: Result<Cid> = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zIII".parse();
@@ -106,3 +130,152 @@ fn to_string_of_base58_v0() {
     let cid = Cid::new_v0(b"foo").unwrap();
     assert_eq!(cid.to_text(None).unwrap(), expected_cid);
 }
+
+#[test]
+fn test_cid_eq_base() {
+    let cid1 = {
+        let base = Base::Base32Lower;
+        Cid::new_v1(base, multicodec::DAG_CBOR.into(), b"beep boop").unwrap()
+    };
+    let cid2 = {
+        let text = cid1.to_text(Some(Base::Base58Btc)).unwrap();
+        Cid::from_text(&text).unwrap()
+    };
+    assert_eq!(cid2.to_base(), Base::Base58Btc);
+    assert_eq!(cid1, cid2);
+    assert_eq!(cid1.cmp(&cid2), cmp::Ordering::Equal);
+
+    let digest = |cid: &Cid| {
+        let mut hasher = DefaultHasher::new();
+        cid.hash(&mut hasher);
+        hasher.finish()
+    };
+    assert_eq!(digest(&cid1), digest(&cid2));
+
+    let set: HashSet<Cid> = vec![cid1.clone(), cid2].into_iter().collect();
+    assert_eq!(set.len(), 1);
+
+    let cid3 = {
+        let base = Base::Base32Lower;
+        Cid::new_v1(base, multicodec::DAG_CBOR.into(), b"beep beep").unwrap()
+    };
+    assert_ne!(cid1, cid3);
+
+    // same multihash, different version.
+    let cid4 = Cid::new_v0(b"beep boop").unwrap();
+    let cid5 = Cid::new_v1(Base::Base58Btc, multicodec::DAG_PB.into(), b"beep boop").unwrap();
+    assert_ne!(cid4, cid5);
+    assert_ne!(digest(&cid4), digest(&cid5));
+}
+
+#[test]
+fn test_cid_prefix() {
+    let cid = Cid::new_v1(Base::Base32Lower, multicodec::DAG_CBOR.into(), b"one").unwrap();
+    let prefix = cid.prefix().unwrap();
+    assert_eq!(prefix.version, Version::One);
+    assert_eq!(prefix.codec, multicodec::DAG_CBOR.into());
+    assert_eq!(prefix.hash_code, multicodec::SHA2_256.into());
+    assert_eq!(prefix.hash_len, 32);
+    assert!(prefix.matches(&cid));
+
+    // same shape, different content and base.
+    let other = Cid::new_v1(Base::Base58Btc, multicodec::DAG_CBOR.into(), b"two").unwrap();
+    assert_ne!(cid, other);
+    assert!(prefix.matches(&other));
+
+    let other = Cid::new_v1(Base::Base32Lower, multicodec::RAW.into(), b"one").unwrap();
+    assert!(!prefix.matches(&other));
+
+    let other = Cid::new_v0(b"one").unwrap();
+    assert_eq!(other.prefix().unwrap().version, Version::Zero);
+    assert!(!prefix.matches(&other));
+    assert!(other
+        .prefix()
+        .unwrap()
+        .matches(&Cid::new_v0(b"two").unwrap()));
+}
+
+#[test]
+fn test_cid_from_parts() {
+    let data = b"beep boop";
+    let mh = Multihash::new(multicodec::SHA2_256.into(), data)
+        .unwrap()
+        .encode()
+        .unwrap();
+
+    let cid = Cid::from_parts_v1(multicodec::DAG_CBOR as u64, mh.clone()).unwrap();
+    let refv = Cid::new_v1(Base::Base32Lower, multicodec::DAG_CBOR.into(), data).unwrap();
+    assert_eq!(cid, refv);
+
+    let cid = Cid::from_parts_v0(mh.clone()).unwrap();
+    assert_eq!(cid, Cid::new_v0(data).unwrap());
+
+    // trailing bytes and truncated digest.
+    let mut bad = mh.clone();
+    bad.push(0);
+    assert!(Cid::from_parts_v1(multicodec::RAW as u64, bad.clone()).is_err());
+    assert!(Cid::from_parts_v0(bad).is_err());
+    assert!(Cid::from_parts_v1(multicodec::RAW as u64, mh[..mh.len() - 1].to_vec()).is_err());
+
+    // v0 rejects everything but 32-byte sha2-256.
+    let mh = Multihash::new(multicodec::SHA2_512.into(), data)
+        .unwrap()
+        .encode()
+        .unwrap();
+    let err = Cid::from_parts_v0(mh.clone()).unwrap_err();
+    assert!(matches!(err, Error::BadInput(..)), "{}", err);
+    let cid = Cid::from_parts_v1(multicodec::RAW as u64, mh).unwrap();
+    assert_eq!(
+        cid.to_multihash().to_codec().unwrap(),
+        multicodec::SHA2_512.into()
+    );
+}
+
+#[test]
+fn test_cid_validate() {
+    let cid = Cid::new_v1(Base::Base32Lower, multicodec::DAG_CBOR.into(), b"valid").unwrap();
+    cid.validate().unwrap();
+    Cid::new_v0(b"valid").unwrap().validate().unwrap();
+    let mh = Multihash::new(multicodec::BLAKE2B_256.into(), b"valid").unwrap();
+    Cid::from_raw(Base::Base32Lower, multicodec::RAW.into(), mh)
+        .validate()
+        .unwrap();
+
+    // declares sha2-256 but carries a 16-byte digest.
+    let mut data = vec![0x01, 0x71, 0x12, 0x10];
+    data.extend_from_slice(&[0xab; 16]);
+    let (cid, rem) = Cid::decode(&data).unwrap();
+    assert!(rem.is_empty());
+    let err = cid.validate().unwrap_err();
+    assert!(matches!(err, Error::BadInput(..)), "{}", err);
+
+    // identity hash carries digest of any length.
+    let mut data = vec![0x01, 0x55, 0x00, 0x03];
+    data.extend_from_slice(b"abc");
+    let (cid, _) = Cid::decode(&data).unwrap();
+    cid.validate().unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_cid_serde() {
+    let cid = Cid::new_v1(Base::Base32Lower, multicodec::DAG_CBOR.into(), b"serde").unwrap();
+
+    let text = serde_json::to_string(&cid).unwrap();
+    assert_eq!(text, format!("\"{}\"", cid.to_text(None).unwrap()));
+    assert_eq!(serde_json::from_str::<Cid>(&text).unwrap(), cid);
+
+    let data = bincode::serialize(&cid).unwrap();
+    let bytes = cid.encode().unwrap();
+    assert!(data.ends_with(&bytes));
+    assert_eq!(bincode::deserialize::<Cid>(&data).unwrap(), cid);
+
+    let cid = Cid::new_v0(b"serde").unwrap();
+    let text = serde_json::to_string(&cid).unwrap();
+    assert_eq!(serde_json::from_str::<Cid>(&text).unwrap(), cid);
+    let data = bincode::serialize(&cid).unwrap();
+    assert_eq!(bincode::deserialize::<Cid>(&data).unwrap(), cid);
+
+    assert!(serde_json::from_str::<Cid>("\"not-a-cid\"").is_err());
+    assert!(serde_json::from_str::<Cid>("10").is_err());
+}
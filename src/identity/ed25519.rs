@@ -22,6 +22,7 @@
 
 use ed25519_dalek::{self as ed25519, Signer as _, Verifier as _};
 use rand::RngCore;
+use subtle::ConstantTimeEq;
 use zeroize::Zeroize;
 
 use std::{convert::TryFrom, fmt};
@@ -132,11 +133,20 @@ impl Keypair {
 }
 
 /// An Ed25519 public key.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct PublicKey {
     public_key: ed25519::PublicKey,
 }
 
+impl Eq for PublicKey {}
+
+/// Compare public keys in constant time.
+impl PartialEq for PublicKey {
+    fn eq(&self, other: &PublicKey) -> bool {
+        self.encode().ct_eq(&other.encode()).into()
+    }
+}
+
 impl PublicKey {
     /// Verify the Ed25519 signature on a message using the public key.
     pub fn verify(&self, msg: &[u8], signature: &[u8]) -> bool {
@@ -178,6 +188,18 @@ impl fmt::Debug for SecretKey {
     }
 }
 
+impl Eq for SecretKey {}
+
+/// Compare secret keys in constant time.
+impl PartialEq for SecretKey {
+    fn eq(&self, other: &SecretKey) -> bool {
+        self.secret_key
+            .as_bytes()
+            .ct_eq(other.secret_key.as_bytes())
+            .into()
+    }
+}
+
 impl SecretKey {
     // TODO: should we try drand.love ?
     /// Generate a new Ed25519 secret key.
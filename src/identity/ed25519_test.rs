@@ -3,7 +3,7 @@ use quickcheck::*;
 
 fn eq_keypairs(kp1: &Keypair, kp2: &Keypair) -> bool {
     let ok = kp1.to_public_key() == kp2.to_public_key();
-    ok && (kp1.key_pair.secret.as_bytes() == kp2.key_pair.secret.as_bytes())
+    ok && (kp1.to_secret_key().unwrap() == kp2.to_secret_key().unwrap())
 }
 
 #[test]
@@ -44,3 +44,14 @@ fn ed25519_signature() {
     let invalid_msg = "h3ll0 w0rld".as_bytes();
     assert!(!pk.verify(invalid_msg, &sig));
 }
+
+#[test]
+fn ed25519_key_eq() {
+    let (kp1, kp2) = (Keypair::generate().unwrap(), Keypair::generate().unwrap());
+
+    assert_eq!(kp1.to_public_key(), kp1.to_public_key());
+    assert_ne!(kp1.to_public_key(), kp2.to_public_key());
+
+    assert_eq!(kp1.to_secret_key().unwrap(), kp1.to_secret_key().unwrap());
+    assert_ne!(kp1.to_secret_key().unwrap(), kp2.to_secret_key().unwrap());
+}
@@ -35,6 +35,28 @@ fn ed25519_api() {
         .verify("hello world".as_bytes(), &signature))
 }
 
+#[test]
+fn keypair_protobuf() {
+    let msg = "hello world".as_bytes();
+
+    let mut kps = vec![Keypair::generate_ed25519().unwrap()];
+    #[cfg(feature = "secp256k1")]
+    kps.push(Keypair::generate_secp256k1().unwrap());
+
+    for kp in kps.into_iter() {
+        let data = kp.to_protobuf_encoding().unwrap();
+        let kp2 = Keypair::from_protobuf_encoding(&data).unwrap();
+        assert_eq!(kp.to_public_key(), kp2.to_public_key());
+
+        let signature = kp2.sign(msg).unwrap();
+        assert!(kp.to_public_key().verify(msg, &signature))
+    }
+
+    let mut key = RSA_KEY.to_vec();
+    let kp = Keypair::from_rsa_pkcs8(&mut key).unwrap();
+    assert!(kp.to_protobuf_encoding().is_err());
+}
+
 //#[test]
 //fn secp256k1_api() {
 //    let kp = Keypair::generate_secp256k1().unwrap();
@@ -116,7 +116,63 @@ impl Keypair {
             #[cfg(not(target_arch = "wasm32"))]
             Rsa(ref pair) => pair.sign(msg),
             #[cfg(feature = "secp256k1")]
-            Secp256k1(ref pair) => pair.as_secret_key().sign(msg),
+            Secp256k1(ref pair) => pair.as_secret_key().sign(msg).map(|s| s.to_bytes()),
+        }
+    }
+
+    /// Encode the keypair into a protobuf structure for storage. Ed25519
+    /// keypair is encoded as secret-key followed by the public-key, and
+    /// Secp256k1 keypair is encoded as raw bytes of the secret-key.
+    pub fn to_protobuf_encoding(&self) -> Result<Vec<u8>> {
+        use prost::Message;
+
+        let private_key = match self {
+            Keypair::Ed25519(pair) => key_pair_proto::PrivateKey {
+                r#type: key_pair_proto::KeyType::Ed25519 as i32,
+                data: pair.encode().to_vec(),
+            },
+            #[cfg(not(target_arch = "wasm32"))]
+            Keypair::Rsa(_) => err_at!(EncodeError, msg: "RSA keypair encoding not supported")?,
+            #[cfg(feature = "secp256k1")]
+            Keypair::Secp256k1(pair) => key_pair_proto::PrivateKey {
+                r#type: key_pair_proto::KeyType::Secp256k1 as i32,
+                data: pair.as_secret_key().to_bytes().to_vec(),
+            },
+        };
+
+        let mut buf = Vec::with_capacity(private_key.encoded_len());
+        err_at!(EncodeError, private_key.encode(&mut buf))?;
+        Ok(buf)
+    }
+
+    /// Decode a keypair from a protobuf structure, in the format produced
+    /// by [Keypair::to_protobuf_encoding].
+    pub fn from_protobuf_encoding(bytes: &[u8]) -> Result<Keypair> {
+        use prost::Message;
+
+        let mut privkey = err_at!(DecodeError, key_pair_proto::PrivateKey::decode(bytes))?;
+
+        let key_type = match key_pair_proto::KeyType::from_i32(privkey.r#type) {
+            Some(typ) => Ok(typ),
+            None => err_at!(DecodeError, msg: "unknown key type: {}", privkey.r#type),
+        }?;
+
+        match key_type {
+            key_pair_proto::KeyType::Ed25519 => {
+                ed25519::Keypair::decode(&mut privkey.data).map(Keypair::Ed25519)
+            }
+            key_pair_proto::KeyType::Rsa => {
+                err_at!(DecodeError, msg: "RSA keypair decoding not supported")
+            }
+            #[cfg(feature = "secp256k1")]
+            key_pair_proto::KeyType::Secp256k1 => {
+                let secret_key = secp256k1::SecretKey::from_bytes(&mut privkey.data)?;
+                Ok(Keypair::Secp256k1(secp256k1::Keypair::from(secret_key)))
+            }
+            #[cfg(not(feature = "secp256k1"))]
+            key_pair_proto::KeyType::Secp256k1 => {
+                err_at!(DecodeError, msg: "secp256k1 disabled at compile-time")
+            }
         }
     }
 
@@ -156,7 +212,10 @@ impl PublicKey {
             #[cfg(not(target_arch = "wasm32"))]
             Rsa(pk) => pk.verify(msg, sig),
             #[cfg(feature = "secp256k1")]
-            Secp256k1(pk) => pk.verify(msg, sig),
+            Secp256k1(pk) => match secp256k1::Signature::from_bytes(sig) {
+                Ok(sig) => pk.verify(msg, &sig),
+                Err(_) => false,
+            },
         }
     }
 
@@ -25,6 +25,7 @@ use lazy_static::lazy_static;
 use ring::rand::SystemRandom;
 use ring::signature::KeyPair;
 use ring::signature::{RsaKeyPair, RSA_PKCS1_2048_8192_SHA256, RSA_PKCS1_SHA256};
+use subtle::ConstantTimeEq;
 use zeroize::Zeroize;
 
 use std::{
@@ -81,11 +82,20 @@ impl Keypair {
 }
 
 /// An RSA public key.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct PublicKey {
     bin: Vec<u8>,
 }
 
+impl Eq for PublicKey {}
+
+/// Compare public keys in constant time.
+impl PartialEq for PublicKey {
+    fn eq(&self, other: &PublicKey) -> bool {
+        self.bin.ct_eq(&other.bin).into()
+    }
+}
+
 impl PublicKey {
     /// Verify an RSA signature on a message using the public key.
     pub fn verify(&self, msg: &[u8], signature: &[u8]) -> bool {
@@ -22,9 +22,10 @@
 
 use asn1_der::{DerObject, FromDerObject};
 use rand::RngCore;
-use secp256k1::{Message, Signature};
+use secp256k1::Message;
 use sha2::{Digest as ShaDigestTrait, Sha256};
-use zeroize::Zeroize;
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, Zeroizing};
 
 use std::fmt;
 
@@ -70,9 +71,7 @@ impl fmt::Debug for Keypair {
 /// Promote a Secp256k1 secret key into a keypair.
 impl From<SecretKey> for Keypair {
     fn from(val: SecretKey) -> Keypair {
-        let public_key = PublicKey {
-            public_key: secp256k1::PublicKey::from_secret_key(&val.secret_key),
-        };
+        let public_key = val.to_public_key();
         Keypair {
             secret_key: SecretKey {
                 secret_key: val.secret_key,
@@ -101,6 +100,18 @@ impl fmt::Debug for SecretKey {
     }
 }
 
+impl Eq for SecretKey {}
+
+/// Compare secret keys in constant time. Serialized copies of the keys
+/// are zeroed once compared.
+impl PartialEq for SecretKey {
+    fn eq(&self, other: &SecretKey) -> bool {
+        let a = Zeroizing::new(self.to_bytes());
+        let b = Zeroizing::new(other.to_bytes());
+        a[..].ct_eq(&b[..]).into()
+    }
+}
+
 impl SecretKey {
     // TODO: should we try drand.love ?
     /// Generate a new Secp256k1 secret key.
@@ -164,12 +175,20 @@ impl SecretKey {
         Ok(sk)
     }
 
-    /// Sign a message with this secret key, producing a DER-encoded
-    /// ECDSA signature, as defined in [RFC3278].
+    /// Sign a message with this secret key, producing an ECDSA signature,
+    /// that can be serialized into DER-encoding as defined in [RFC3278].
+    ///
+    /// Same as [SecretKey::sign_sha256], which is the hashing policy
+    /// used by libp2p for secp256k1 keys.
     ///
     /// [RFC3278]: https://tools.ietf.org/html/rfc3278#section-8.2
-    pub fn sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
-        self.sign_hash(Sha256::digest(msg).as_ref())
+    pub fn sign(&self, msg: &[u8]) -> Result<Signature> {
+        self.sign_sha256(msg)
+    }
+
+    /// Hash the message with SHA2-256 and sign the 32-byte digest.
+    pub fn sign_sha256(&self, msg: &[u8]) -> Result<Signature> {
+        self.sign_prehashed(Sha256::digest(msg).as_ref())
     }
 
     /// Returns the raw bytes of the secret key.
@@ -177,37 +196,78 @@ impl SecretKey {
         self.secret_key.serialize()
     }
 
-    /// Sign a raw message of length 256 bits with this secret key, produces a DER-encoded
-    /// ECDSA signature.
-    fn sign_hash(&self, msg: &[u8]) -> Result<Vec<u8>> {
-        let m = match Message::parse_slice(msg) {
+    /// Return the public key corresponding to this secret key.
+    pub fn to_public_key(&self) -> PublicKey {
+        PublicKey {
+            public_key: secp256k1::PublicKey::from_secret_key(&self.secret_key),
+        }
+    }
+
+    /// Sign a pre-computed digest of length 256 bits with this secret key.
+    /// Use this when caller has hashed the message by itself, `digest`
+    /// of any other length is an error.
+    pub fn sign_prehashed(&self, digest: &[u8]) -> Result<Signature> {
+        if digest.len() != 32 {
+            err_at!(SigningError, msg: "secp256k1 digest-len {} != 32", digest.len())?
+        }
+        let m = match Message::parse_slice(digest) {
             Ok(m) => Ok(m),
             err @ Err(_) => err_at!(SigningError, err, "secp256k1 digest"),
         }?;
-        Ok(secp256k1::sign(&m, &self.secret_key)
-            .0
-            .serialize_der()
-            .as_ref()
-            .into())
+        let signature = secp256k1::sign(&m, &self.secret_key).0;
+        Ok(Signature { signature })
     }
 }
 
-/// A Secp256k1 public key.
+/// A Secp256k1 ECDSA signature.
 #[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Signature {
+    signature: secp256k1::Signature,
+}
+
+impl Signature {
+    /// Decode a DER-encoded signature, in the format produced by
+    /// `to_bytes`.
+    pub fn from_bytes(der: &[u8]) -> Result<Signature> {
+        match secp256k1::Signature::parse_der(der) {
+            Ok(signature) => Ok(Signature { signature }),
+            Err(err) => err_at!(DecodeError, Err(err), "secp256k1 signature"),
+        }
+    }
+
+    /// Encode the signature in DER format, as defined in [RFC3278].
+    ///
+    /// [RFC3278]: https://tools.ietf.org/html/rfc3278#section-8.2
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.signature.serialize_der().as_ref().to_vec()
+    }
+}
+
+/// A Secp256k1 public key.
+#[derive(Clone, Debug)]
 pub struct PublicKey {
     public_key: secp256k1::PublicKey,
 }
 
+impl Eq for PublicKey {}
+
+/// Compare public keys in constant time.
+impl PartialEq for PublicKey {
+    fn eq(&self, other: &PublicKey) -> bool {
+        self.encode_uncompressed()
+            .ct_eq(&other.encode_uncompressed())
+            .into()
+    }
+}
+
 impl PublicKey {
     /// Verify the Secp256k1 signature on a message using the public key.
-    pub fn verify(&self, msg: &[u8], signature: &[u8]) -> bool {
+    pub fn verify(&self, msg: &[u8], signature: &Signature) -> bool {
         let msg = Sha256::digest(msg);
-        // Verify the Secp256k1 DER-encoded signature on a raw 256-bit hash
-        // using the public key.
+        // Verify the Secp256k1 signature on a raw 256-bit hash using the
+        // public key.
         Message::parse_slice(msg.as_ref())
-            .and_then(|m| {
-                Signature::parse_der(signature).map(|s| secp256k1::verify(&m, &s, &self.public_key))
-            })
+            .map(|m| secp256k1::verify(&m, &signature.signature, &self.public_key))
             .unwrap_or(false)
     }
 
@@ -5,6 +5,53 @@ fn secp256k1_secret_from_bytes() {
     let sk1 = SecretKey::generate();
     let mut sk_bytes = sk1.to_bytes();
     let sk2 = SecretKey::from_bytes(&mut sk_bytes).unwrap();
-    assert_eq!(sk1.secret_key.serialize(), sk2.secret_key.serialize());
+    assert_eq!(sk1, sk2);
     assert_eq!(sk_bytes, [0; 32]);
 }
+
+#[test]
+fn secp256k1_signature() {
+    let sk = SecretKey::generate();
+    let pk = sk.to_public_key();
+    assert_eq!(pk, Keypair::from(sk.clone()).to_public_key());
+
+    let msg = "hello world".as_bytes();
+    let sig = sk.sign(msg).unwrap();
+    assert!(pk.verify(msg, &sig));
+
+    let sig = Signature::from_bytes(&sig.to_bytes()).unwrap();
+    assert!(pk.verify(msg, &sig));
+
+    let invalid_msg = "h3ll0 w0rld".as_bytes();
+    assert!(!pk.verify(invalid_msg, &sig));
+
+    assert!(Signature::from_bytes(&[0x30, 0x01]).is_err());
+}
+
+#[test]
+fn secp256k1_sign_prehashed() {
+    let sk = SecretKey::generate();
+    let pk = sk.to_public_key();
+
+    let msg = "hello world".as_bytes();
+    let sig = sk.sign_sha256(msg).unwrap();
+    assert!(pk.verify(msg, &sig));
+
+    let digest = Sha256::digest(msg);
+    let sig = sk.sign_prehashed(digest.as_ref()).unwrap();
+    assert!(pk.verify(msg, &sig));
+
+    assert!(sk.sign_prehashed(&digest[..31]).is_err());
+    assert!(sk.sign_prehashed(msg).is_err());
+}
+
+#[test]
+fn secp256k1_key_eq() {
+    let (sk1, sk2) = (SecretKey::generate(), SecretKey::generate());
+
+    assert_eq!(sk1, sk1.clone());
+    assert_ne!(sk1, sk2);
+
+    assert_eq!(sk1.to_public_key(), sk1.to_public_key());
+    assert_ne!(sk1.to_public_key(), sk2.to_public_key());
+}
@@ -5,9 +5,24 @@
 //!
 //! [cid]: https://github.com/multiformats/cid
 
-use std::{fmt, result};
+use multibase::Base;
 
-use crate::{cid::Cid, multihash::Multihash, Result};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fmt, io, result,
+};
+
+use crate::{
+    cid::Cid,
+    ipld::{
+        cbor::Cbor,
+        kind::{Basic, Node},
+    },
+    multicodec::{self, Multicodec},
+    multihash::Multihash,
+    Error, Result,
+};
 
 /// Block composed of Cid and opaque-data.
 pub struct Block {
@@ -53,13 +68,258 @@ impl Block {
         Ok(self.cid.to_multihash())
     }
     /// Verify whether the multihash in Cid, matches with the block's
-    /// opaque data.
+    /// opaque data. Refer [verify_block] for details.
     pub fn verify(&self) -> Result<bool> {
-        let mh = match &self.cid {
-            Cid::Zero(mh) => mh,
-            Cid::One(_, _, mh) => mh,
+        verify_block(&self.cid, &self.data)
+    }
+}
+
+/// In-memory block store, blocks are keyed and de-duplicated by their CID.
+#[derive(Default)]
+pub struct BlockStore {
+    blocks: BTreeMap<Cid, Vec<u8>>,
+}
+
+impl BlockStore {
+    /// Create a new empty store.
+    pub fn new() -> BlockStore {
+        BlockStore::default()
+    }
+
+    /// Compute CIDv1 for `block`, with multicodec `codec` and hashing it
+    /// using multihash algorithm `hash`, and store the block keyed by the
+    /// CID. Putting the same block again is a no-op. Return the CID.
+    pub fn put(&mut self, block: Vec<u8>, codec: u64, hash: u64) -> Result<Cid> {
+        let cid = make_cid(&block, codec, hash)?;
+        self.blocks.entry(cid.clone()).or_insert(block);
+        Ok(cid)
+    }
+
+    /// Return the block data for `cid`, if present.
+    pub fn get(&self, cid: &Cid) -> Option<&[u8]> {
+        self.blocks.get(cid).map(|data| data.as_slice())
+    }
+
+    /// Return whether block for `cid` is present.
+    pub fn has(&self, cid: &Cid) -> bool {
+        self.blocks.contains_key(cid)
+    }
+
+    /// Return the number of blocks in the store.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Return whether store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+/// Compute CIDv1 for the `node` encoded using multicodec `codec`, and
+/// hashed using multihash algorithm `hash`, like sha2-256, sha2-512 or
+/// blake2b-256. Only dag-cbor codec is supported.
+pub fn cid_of(node: &dyn Node, codec: u64, hash: u64) -> Result<Cid> {
+    match codec as u128 {
+        multicodec::DAG_CBOR => make_cid(&node.to_dag_cbor_bytes()?, codec, hash),
+        _ => err_at!(BadCodec, msg: "can't encode node with codec {}", codec),
+    }
+}
+
+/// Same as [cid_of], but children of a list or map `node` are validated
+/// and encoded in parallel, using rayon's thread-pool, before they are
+/// combined into block data and hashed. Return the same CID as [cid_of].
+#[cfg(feature = "parallel")]
+pub fn cid_of_parallel(node: &dyn Node, codec: u64, hash: u64) -> Result<Cid> {
+    use rayon::prelude::*;
+
+    use crate::ipld::{
+        cbor::{encode_head, Major},
+        kind::{Key, Kind},
+    };
+
+    if codec as u128 != multicodec::DAG_CBOR {
+        return err_at!(BadCodec, msg: "can't encode node with codec {}", codec);
+    }
+
+    let (major, parts) = match node.to_kind() {
+        Kind::List => {
+            let items: Vec<&dyn Node> = node.iter().collect();
+            let parts: Result<Vec<Vec<u8>>> = items
+                .into_par_iter()
+                .map(|item| item.to_dag_cbor_bytes())
+                .collect();
+            (Major::M4, parts?)
+        }
+        Kind::Map => {
+            let mut entries: Vec<(Key, &dyn Node)> = vec![];
+            for (key, value) in node.iter_entries() {
+                if !key.is_dag_cbor() {
+                    err_at!(FailCbor, msg: "non-text map key {}", key)?
+                }
+                entries.push((key, value));
+            }
+            entries.sort_by(|(a, _), (b, _)| a.cmp_canonical(b));
+
+            let parts: Result<Vec<Vec<u8>>> = entries
+                .into_par_iter()
+                .map(|(key, value)| -> Result<Vec<u8>> {
+                    let mut buf = vec![];
+                    Cbor::from(&key).encode(&mut buf)?;
+                    buf.extend_from_slice(&value.to_dag_cbor_bytes()?);
+                    Ok(buf)
+                })
+                .collect();
+            (Major::M5, parts?)
+        }
+        _ => return cid_of(node, codec, hash),
+    };
+
+    let mut data = vec![];
+    encode_head(major, parts.len() as u64, &mut data)?;
+    parts.iter().for_each(|part| data.extend_from_slice(part));
+
+    make_cid(&data, codec, hash)
+}
+
+/// Walk the DAG rooted at `root`, decoding each block from `store` and
+/// calling `visit` on it, and then following its links in pre-order. Every
+/// block is visited once, even if it is linked from several blocks. Raw
+/// blocks are visited as [Basic::Bytes] and dag-cbor blocks are decoded
+/// into [Basic] value.
+///
+/// Links that are missing in `store` are skipped and returned, in the
+/// order they are found.
+pub fn walk_dag(
+    store: &BlockStore,
+    root: &Cid,
+    visit: &mut dyn FnMut(&Cid, &dyn Node),
+) -> Result<Vec<Cid>> {
+    let mut seen: BTreeSet<Cid> = BTreeSet::new();
+    let mut missing = vec![];
+
+    let mut stack = vec![root.clone()];
+    while let Some(cid) = stack.pop() {
+        if !seen.insert(cid.clone()) {
+            continue;
+        }
+        let data = match store.get(&cid) {
+            Some(data) => data,
+            None => {
+                missing.push(cid);
+                continue;
+            }
         };
-        let computed_mh = Multihash::new(mh.to_codec()?, &self.data)?;
-        Ok(mh == &computed_mh)
+        let node = match cid.to_content_type().to_code() {
+            multicodec::RAW => Basic::Bytes(data.into()),
+            multicodec::DAG_CBOR => {
+                let mut r: &[u8] = data;
+                Basic::try_from(Cbor::decode(&mut r)?)?
+            }
+            _ => err_at!(BadCodec, msg: "can't walk block {}", cid)?,
+        };
+        visit(&cid, &node);
+
+        // push in reverse so that links are walked in the order they
+        // appear within the block.
+        for (_, link) in node.links().into_iter().rev() {
+            if !seen.contains(&link) {
+                stack.push(link)
+            }
+        }
+    }
+
+    Ok(missing)
+}
+
+fn make_cid(data: &[u8], codec: u64, hash: u64) -> Result<Cid> {
+    let content_type = Multicodec::from_code(codec.into())?;
+    let hash = Multicodec::from_code(hash.into())?;
+    Cid::new_v1_with_hash(Base::Base32Lower, content_type, hash, data)
+}
+
+/// Create a CIDv1 for raw leaf block, with multicodec _raw_ (0x55),
+/// hashing `data` using the multihash algorithm `hash`.
+pub fn raw_block_cid(data: &[u8], hash: u64) -> Result<Cid> {
+    make_cid(data, multicodec::RAW as u64, hash)
+}
+
+/// Verify whether the multihash in `cid` matches with block's `data`.
+/// Raw blocks are opaque bytes, only the hash is verified. For dag-cbor
+/// blocks, data is also checked to be a valid cbor value. For blake3,
+/// digest is computed to the length specified by the multihash.
+pub fn verify_block(cid: &Cid, data: &[u8]) -> Result<bool> {
+    let mh = cid.to_multihash();
+    let codec = mh.to_codec()?;
+    let computed_mh = match codec.to_code() {
+        multicodec::BLAKE3 => Multihash::new_blake3(data, mh.to_digest()?.len())?,
+        _ => Multihash::new(codec, data)?,
+    };
+    if mh != computed_mh {
+        return Ok(false);
     }
+
+    match cid.to_content_type().to_code() {
+        multicodec::RAW => Ok(true),
+        multicodec::DAG_CBOR => {
+            let mut r: &[u8] = data;
+            Ok(Cbor::decode(&mut r).is_ok() && r.is_empty())
+        }
+        _ => Ok(true),
+    }
+}
+
+/// Write `bytes` as a frame into `w`, prefixed with its length encoded as
+/// unsigned-varint. Return the number of bytes written.
+pub fn write_block<W: io::Write>(w: &mut W, bytes: &[u8]) -> Result<usize> {
+    use unsigned_varint::encode as uve;
+
+    let mut buf = [0_u8; 10];
+    let prefix = uve::usize(bytes.len(), &mut buf);
+    err_at!(IOError, w.write_all(prefix))?;
+    err_at!(IOError, w.write_all(bytes))?;
+
+    Ok(prefix.len() + bytes.len())
+}
+
+/// Read a frame, written using [write_block], from `r`. Return None if `r`
+/// is at clean EOF, that is, there are no more bytes before the length
+/// prefix. A partial frame is treated as error.
+pub fn read_block<R: io::Read>(r: &mut R) -> Result<Option<Vec<u8>>> {
+    use unsigned_varint::decode as uvd;
+
+    let mut buf = [0_u8; 10];
+    let mut i = 0;
+    loop {
+        match err_at!(IOError, r.read(&mut buf[i..=i]))? {
+            0 if i == 0 => return Ok(None),
+            0 => err_at!(DecodeError, msg: "truncated length prefix")?,
+            _ => i += 1,
+        }
+        match buf[i - 1] & 0x80 {
+            0 => break,
+            _ if i == buf.len() => err_at!(DecodeError, msg: "length prefix overflow")?,
+            _ => (),
+        }
+    }
+
+    // length prefix is untrusted, read only as much as `r` has, instead of
+    // allocating `n` bytes upfront.
+    let (n, _) = err_at!(DecodeError, uvd::u64(&buf[..i]))?;
+    let mut data = vec![];
+    err_at!(IOError, r.by_ref().take(n).read_to_end(&mut data))?;
+    if (data.len() as u64) != n {
+        err_at!(
+            DecodeError,
+            msg: "truncated frame of {} bytes, got {}",
+            n,
+            data.len()
+        )?
+    }
+
+    Ok(Some(data))
 }
+
+#[cfg(test)]
+#[path = "block_test.rs"]
+mod block_test;
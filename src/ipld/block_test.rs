@@ -0,0 +1,257 @@
+use super::*;
+use crate::ipld::kind::Kind;
+
+#[test]
+fn test_blake3_cid() {
+    let data = b"hello blake3 leaf";
+    let cid = raw_block_cid(data, multicodec::BLAKE3 as u64).unwrap();
+    let sha = raw_block_cid(data, multicodec::SHA2_256 as u64).unwrap();
+    assert_ne!(cid, sha);
+
+    let mh = cid.to_multihash();
+    assert_eq!(mh.to_codec().unwrap(), multicodec::BLAKE3.into());
+    assert_eq!(mh.to_digest().unwrap().len(), 32);
+    assert!(verify_block(&cid, data).unwrap());
+    assert!(!verify_block(&cid, b"hello other leaf").unwrap());
+    cid.validate().unwrap();
+
+    // extended output, the 32-byte digest is a prefix of longer ones.
+    let mh = Multihash::new_blake3(data, 64).unwrap();
+    let digest = mh.to_digest().unwrap();
+    assert_eq!(digest.len(), 64);
+    assert_eq!(
+        &digest[..32],
+        cid.to_multihash().to_digest().unwrap().as_slice()
+    );
+
+    let long = Cid::from_raw(Base::Base32Lower, multicodec::RAW.into(), mh);
+    let (long, _) = Cid::decode(&long.encode().unwrap()).unwrap();
+    assert_eq!(long.to_multihash().to_digest().unwrap().len(), 64);
+    assert!(verify_block(&long, data).unwrap());
+    assert!(!verify_block(&long, b"hello other leaf").unwrap());
+    long.validate().unwrap();
+
+    assert!(Multihash::new_blake3(data, 0).is_err());
+}
+
+#[test]
+fn test_raw_block() {
+    let data = b"hello raw leaf".to_vec();
+    let hash = multicodec::SHA2_256 as u64;
+    let cid = raw_block_cid(&data, hash).unwrap();
+
+    assert_eq!(cid.to_content_type(), multicodec::RAW.into());
+    assert_eq!(
+        cid.to_multihash().to_codec().unwrap(),
+        multicodec::SHA2_256.into()
+    );
+    assert!(verify_block(&cid, &data).unwrap());
+    assert!(!verify_block(&cid, b"hello other leaf").unwrap());
+
+    let block = Block::new(cid, data);
+    assert!(block.verify().unwrap());
+
+    let cid = raw_block_cid(b"raw", multicodec::BLAKE3 as u64).unwrap();
+    assert!(verify_block(&cid, b"raw").unwrap());
+}
+
+#[test]
+fn test_block_framing() {
+    let blocks: Vec<Vec<u8>> = vec![b"one".to_vec(), vec![], vec![0xab; 300]];
+
+    let mut buf = vec![];
+    for block in blocks.iter() {
+        let n = write_block(&mut buf, block).unwrap();
+        assert!(n > block.len());
+    }
+    assert_eq!(buf.len(), (1 + 3) + 1 + (2 + 300));
+
+    let mut r: &[u8] = &buf;
+    for block in blocks.iter() {
+        assert_eq!(&read_block(&mut r).unwrap().unwrap(), block);
+    }
+    assert!(read_block(&mut r).unwrap().is_none());
+
+    let mut r: &[u8] = &buf[..buf.len() - 1];
+    read_block(&mut r).unwrap().unwrap();
+    read_block(&mut r).unwrap().unwrap();
+    let err = read_block(&mut r).unwrap_err();
+    assert!(matches!(err, crate::Error::DecodeError(..)), "{}", err);
+
+    let mut r: &[u8] = &[0x80];
+    let err = read_block(&mut r).unwrap_err();
+    assert!(matches!(err, crate::Error::DecodeError(..)), "{}", err);
+
+    // length prefix of 2^56-1 bytes, followed by a short frame.
+    let mut r: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 0x61, 0x62];
+    let err = read_block(&mut r).unwrap_err();
+    assert!(matches!(err, crate::Error::DecodeError(..)), "{}", err);
+    assert!(err.to_string().contains("truncated frame"), "{}", err);
+}
+
+#[test]
+fn test_block_store() {
+    let mut store = BlockStore::new();
+    assert!(store.is_empty());
+
+    let hash = multicodec::SHA2_256 as u64;
+    let raw = multicodec::RAW as u64;
+    let cid1 = store.put(b"one".to_vec(), raw, hash).unwrap();
+    let cid2 = store.put(b"one".to_vec(), raw, hash).unwrap();
+    assert_eq!(cid1, cid2);
+    assert_eq!(store.len(), 1);
+    assert_eq!(cid1, raw_block_cid(b"one", hash).unwrap());
+
+    let cid3 = store.put(b"two".to_vec(), raw, hash).unwrap();
+    assert_ne!(cid1, cid3);
+    assert_eq!(store.len(), 2);
+
+    assert!(store.has(&cid1));
+    assert_eq!(store.get(&cid1).unwrap(), b"one");
+    assert_eq!(store.get(&cid3).unwrap(), b"two");
+    assert!(verify_block(&cid3, store.get(&cid3).unwrap()).unwrap());
+
+    let other = raw_block_cid(b"three", hash).unwrap();
+    assert!(!store.has(&other));
+    assert!(store.get(&other).is_none());
+}
+
+#[test]
+fn test_walk_dag() {
+    use crate::ipld::kind::Key;
+
+    let make_node = |entries: Vec<(&str, Cid)>| -> Vec<u8> {
+        let node: Basic = entries
+            .into_iter()
+            .map(|(k, cid)| (Key::Text(k.to_string()), Basic::Link(cid.into())))
+            .collect();
+        node.to_dag_cbor_bytes().unwrap()
+    };
+
+    let (hash, raw, cbor) = (
+        multicodec::SHA2_256 as u64,
+        multicodec::RAW as u64,
+        multicodec::DAG_CBOR as u64,
+    );
+
+    // root -> {a, b, c}, a -> leaf, b -> {leaf, a}, c is missing.
+    let mut store = BlockStore::new();
+    let leaf = store.put(b"leaf".to_vec(), raw, hash).unwrap();
+    let a = make_node(vec![("leaf", leaf.clone())]);
+    let a = store.put(a, cbor, hash).unwrap();
+    let b = make_node(vec![("leaf", leaf.clone()), ("a", a.clone())]);
+    let b = store.put(b, cbor, hash).unwrap();
+    let c = raw_block_cid(b"missing", hash).unwrap();
+    let entries = vec![("a", a.clone()), ("b", b.clone()), ("c", c.clone())];
+    let root = store.put(make_node(entries), cbor, hash).unwrap();
+
+    let mut visited = vec![];
+    let missing = walk_dag(&store, &root, &mut |cid, node| {
+        visited.push((cid.clone(), node.to_kind()));
+    })
+    .unwrap();
+
+    let refs = vec![
+        (root, Kind::Map),
+        (a, Kind::Map),
+        (leaf, Kind::Bytes),
+        (b, Kind::Map),
+    ];
+    assert_eq!(visited, refs);
+    assert_eq!(missing, vec![c]);
+}
+
+fn make_wide_map(n: usize) -> Basic {
+    use crate::ipld::kind::Key;
+
+    (0..n)
+        .map(|i| {
+            let value: Basic = vec![
+                Basic::Integer(i as i128),
+                Basic::from(format!("value-{}", i)),
+                Basic::Bytes(vec![0xab; i % 64].into()),
+            ]
+            .into_iter()
+            .collect();
+            (Key::Text(format!("key-{}", i)), value)
+        })
+        .collect()
+}
+
+#[test]
+fn test_cid_of() {
+    let (hash, cbor) = (multicodec::SHA2_256 as u64, multicodec::DAG_CBOR as u64);
+
+    let doc = make_wide_map(100);
+    let cid = cid_of(&doc, cbor, hash).unwrap();
+    let mut store = BlockStore::new();
+    let data = doc.to_dag_cbor_bytes().unwrap();
+    assert_eq!(store.put(data.clone(), cbor, hash).unwrap(), cid);
+    assert!(verify_block(&cid, &data).unwrap());
+
+    assert!(cid_of(&doc, multicodec::RAW as u64, hash).is_err());
+    assert!(cid_of(&Basic::Float(f64::NAN), cbor, hash).is_err());
+}
+
+#[test]
+fn test_cid_of_hash() {
+    let cbor = multicodec::DAG_CBOR as u64;
+    let doc = make_wide_map(10);
+    let data = doc.to_dag_cbor_bytes().unwrap();
+
+    for (hash, size) in [(multicodec::SHA2_512, 64), (multicodec::BLAKE2B_256, 32)].iter() {
+        let cid = cid_of(&doc, cbor, *hash as u64).unwrap();
+        let mh = cid.to_multihash();
+        assert_eq!(mh.to_codec().unwrap(), (*hash).into());
+        assert_eq!(mh.to_digest().unwrap().len(), *size);
+        assert!(verify_block(&cid, &data).unwrap());
+        assert!(!verify_block(&cid, b"hello").unwrap());
+
+        let cid = raw_block_cid(b"hello", *hash as u64).unwrap();
+        assert!(verify_block(&cid, b"hello").unwrap());
+    }
+
+    let err = cid_of(&doc, cbor, multicodec::DAG_CBOR as u64).unwrap_err();
+    assert!(matches!(err, crate::Error::NotImplemented(..)), "{}", err);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_cid_of_parallel() {
+    use crate::ipld::kind::Key;
+
+    let (hash, cbor) = (multicodec::SHA2_256 as u64, multicodec::DAG_CBOR as u64);
+
+    let doc = make_wide_map(10_000);
+    let refv = cid_of(&doc, cbor, hash).unwrap();
+    assert_eq!(cid_of_parallel(&doc, cbor, hash).unwrap(), refv);
+
+    let doc: Basic = vec![make_wide_map(10), Basic::Null, make_wide_map(100)]
+        .into_iter()
+        .collect();
+    let refv = cid_of(&doc, cbor, hash).unwrap();
+    assert_eq!(cid_of_parallel(&doc, cbor, hash).unwrap(), refv);
+
+    let doc = Basic::Integer(10);
+    let refv = cid_of(&doc, cbor, hash).unwrap();
+    assert_eq!(cid_of_parallel(&doc, cbor, hash).unwrap(), refv);
+
+    let doc: Basic = vec![Basic::Integer(1), Basic::Float(f64::INFINITY)]
+        .into_iter()
+        .collect();
+    assert!(cid_of_parallel(&doc, cbor, hash).is_err());
+
+    // keyable keys are encoded as text, like in cid_of.
+    let doc: Basic = (0..100_u32)
+        .map(|i| (Key::Keyable(Box::new(i * 7)), Basic::Integer(i as i128)))
+        .chain(vec![(Key::Text("text".to_string()), Basic::Null)])
+        .collect();
+    let refv = cid_of(&doc, cbor, hash).unwrap();
+    assert_eq!(cid_of_parallel(&doc, cbor, hash).unwrap(), refv);
+
+    let doc: Basic = vec![(Key::Offset(1), Basic::Null)].into_iter().collect();
+    let err = cid_of(&doc, cbor, hash).unwrap_err();
+    assert!(matches!(err, crate::Error::FailCbor(..)), "{}", err);
+    let err = cid_of_parallel(&doc, cbor, hash).unwrap_err();
+    assert!(matches!(err, crate::Error::FailCbor(..)), "{}", err);
+}
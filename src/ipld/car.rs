@@ -0,0 +1,131 @@
+//! Module implement CARv1, content addressable archive, reader and writer.
+//!
+//! A CARv1 file is a DAG-CBOR encoded header, carrying the roots and the
+//! version, followed by a sequence of sections. Each section and the
+//! header are framed using [write_block], and a section is the binary
+//! encoded CID followed by block data. _Refer [car] spec for details_.
+//!
+//! [car]: https://ipld.io/specs/transport/car/carv1/
+
+use std::{collections::BTreeMap, convert::TryFrom, io};
+
+use crate::{
+    cid::Cid,
+    ipld::{
+        block::{read_block, verify_block, write_block},
+        cbor::Cbor,
+        kind::{Basic, Key, Node},
+    },
+    Error, Result,
+};
+
+/// CAR format version supported by this module.
+pub const CAR_VERSION: i128 = 1;
+
+/// Write blocks into CARv1 format.
+pub struct CarWriter<W: io::Write> {
+    w: W,
+}
+
+impl<W: io::Write> CarWriter<W> {
+    /// Create a new writer, writing the header with `roots` into `w`.
+    pub fn new(mut w: W, roots: &[Cid]) -> Result<CarWriter<W>> {
+        let roots: Basic = roots.iter().cloned().map(Basic::from).collect();
+        let mut header: BTreeMap<Key, Box<dyn Node>> = BTreeMap::new();
+        header.insert(Key::Text("roots".to_string()), Box::new(roots));
+        let version = Box::new(Basic::Integer(CAR_VERSION));
+        header.insert(Key::Text("version".to_string()), version);
+
+        let data = Basic::Map(Box::new(header)).to_dag_cbor_bytes()?;
+        write_block(&mut w, &data)?;
+
+        Ok(CarWriter { w })
+    }
+
+    /// Write a section, block data along with its cid. Return the number
+    /// of bytes written.
+    pub fn write(&mut self, cid: &Cid, block: &[u8]) -> Result<usize> {
+        let mut data = cid.encode()?;
+        data.extend_from_slice(block);
+        write_block(&mut self.w, &data)
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_writer(mut self) -> Result<W> {
+        err_at!(IOError, self.w.flush())?;
+        Ok(self.w)
+    }
+}
+
+/// Read blocks from CARv1 format.
+pub struct CarReader<R: io::Read> {
+    r: R,
+    roots: Vec<Cid>,
+    verify: bool,
+}
+
+impl<R: io::Read> CarReader<R> {
+    /// Create a new reader, reading the header from `r`.
+    pub fn new(mut r: R) -> Result<CarReader<R>> {
+        let data = match read_block(&mut r)? {
+            Some(data) => data,
+            None => err_at!(DecodeError, msg: "missing car header")?,
+        };
+        let header = {
+            let mut buf: &[u8] = &data;
+            Basic::try_from(Cbor::decode(&mut buf)?)?
+        };
+
+        let version = header.get(&Key::Text("version".to_string()))?;
+        match version.to_integer() {
+            Some(CAR_VERSION) => (),
+            val => err_at!(DecodeError, msg: "invalid car version {:?}", val)?,
+        }
+
+        let mut roots = vec![];
+        for root in header.get(&Key::Text("roots".to_string()))?.iter() {
+            match root.as_link() {
+                Some(cid) => roots.push(cid.clone()),
+                None => err_at!(DecodeError, msg: "invalid car root {:?}", root.to_kind())?,
+            }
+        }
+
+        Ok(CarReader {
+            r,
+            roots,
+            verify: false,
+        })
+    }
+
+    /// Verify every block read against its cid, refer [verify_block].
+    /// Default is false.
+    pub fn set_verify(&mut self, verify: bool) -> &mut Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Return the roots from car header.
+    pub fn to_roots(&self) -> Vec<Cid> {
+        self.roots.clone()
+    }
+
+    /// Read the next section, return block data along with its cid.
+    /// Return None at the end of file.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Option<(Cid, Vec<u8>)>> {
+        let data = match read_block(&mut self.r)? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let (cid, block) = Cid::decode(&data)?;
+        if self.verify && !verify_block(&cid, block)? {
+            err_at!(HashFail, msg: "block does not match {}", cid)?
+        }
+
+        Ok(Some((cid, block.to_vec())))
+    }
+}
+
+#[cfg(test)]
+#[path = "car_test.rs"]
+mod car_test;
@@ -0,0 +1,43 @@
+use multibase::Base;
+
+use super::*;
+use crate::multicodec;
+
+#[test]
+fn test_car_roundtrip() {
+    let leaf = b"hello car".to_vec();
+    let leaf_cid = Cid::new_v1(Base::Base32Lower, multicodec::RAW.into(), &leaf).unwrap();
+
+    let root: Basic = vec![(Key::Text("leaf".to_string()), Basic::from(leaf_cid.clone()))]
+        .into_iter()
+        .collect();
+    let root = root.to_dag_cbor_bytes().unwrap();
+    let root_cid = Cid::new_v1(Base::Base32Lower, multicodec::DAG_CBOR.into(), &root).unwrap();
+
+    let mut buf = {
+        let mut w = CarWriter::new(vec![], &[root_cid.clone()]).unwrap();
+        w.write(&root_cid, &root).unwrap();
+        w.write(&leaf_cid, &leaf).unwrap();
+        w.into_writer().unwrap()
+    };
+
+    let mut r = CarReader::new(buf.as_slice()).unwrap();
+    r.set_verify(true);
+    assert_eq!(r.to_roots(), vec![root_cid.clone()]);
+    assert_eq!(r.next().unwrap(), Some((root_cid, root)));
+    assert_eq!(r.next().unwrap(), Some((leaf_cid, leaf)));
+    assert_eq!(r.next().unwrap(), None);
+
+    // corrupt the last byte of leaf block.
+    let n = buf.len();
+    buf[n - 1] ^= 0xff;
+    let mut r = CarReader::new(buf.as_slice()).unwrap();
+    r.set_verify(true);
+    r.next().unwrap().unwrap();
+    let err = r.next().unwrap_err();
+    assert!(matches!(err, Error::HashFail(..)), "{}", err);
+
+    let mut r = CarReader::new(buf.as_slice()).unwrap();
+    r.next().unwrap().unwrap();
+    r.next().unwrap().unwrap();
+}
@@ -1,63 +1,336 @@
-use std::{
-    collections::BTreeMap,
-    convert::{TryFrom, TryInto},
-    io,
-};
+#[cfg(feature = "std")]
+use multibase::Base;
+
+use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
+use core::cmp;
+use core::convert::{TryFrom, TryInto};
+#[cfg(feature = "std")]
+use core::mem;
+// TODO: io is the only std dependency for encoding and decoding, replace
+// it with slice based reader/writer for no_std targets.
+#[cfg(feature = "std")]
+use std::io;
 
-use crate::{cid::Cid, ipld::kind::Node, Error, Result};
+#[cfg(feature = "std")]
+use crate::ipld::kind::{Basic, KeyInterner};
+#[cfg(not(feature = "std"))]
+use crate::no_std_prelude::*;
+use crate::{
+    cid::Cid,
+    ipld::kind::{Key, Node},
+    Error, Result,
+};
 
 // TODO: https://github.com/cbor/test-vectors
 
 /// TAG ID for IPLD Content identifier, registered with IANA.
 pub const TAG_IPLD_CID: u64 = 42;
 
+/// TAG ID for unsigned bignum, refer RFC 8949.
+pub const TAG_UBIGNUM: u64 = 2;
+
+/// TAG ID for negative bignum, refer RFC 8949.
+pub const TAG_NBIGNUM: u64 = 3;
+
 /// Recursion limit for nested Cbor objects.
 pub const RECURSION_LIMIT: u32 = 1000;
 
 /// Cbor type, sole purpose is to correspond with [Basic] data-model.
-#[derive(Clone)]
+///
+/// Equality is structural, values are equal if they are of the same major
+/// type, with same additional info and same content. Map entries are
+/// compared in the order they are held, and floats by their bit pattern.
+#[derive(Clone, Eq, PartialEq)]
 pub enum Cbor {
-    Major0(Info, u64),                    // uint 0-23,24,25,26,27
-    Major1(Info, u64),                    // nint 0-23,24,25,26,27
-    Major2(Info, Vec<u8>),                // byts 0-23,24,25,26,27,31
-    Major3(Info, Vec<u8>),                // text 0-23,24,25,26,27,31
-    Major4(Info, Vec<Cbor>),              // list 0-23,24,25,26,27,31
-    Major5(Info, BTreeMap<String, Cbor>), // dict 0-23,24,25,26,27,31
-    Major6(Info, Tag),                    // tags similar to major0
-    Major7(Info, SimpleValue),            // type refer SimpleValue
+    Major0(Info, u64),              // uint 0-23,24,25,26,27
+    Major1(Info, u64),              // nint 0-23,24,25,26,27
+    Major2(Info, Vec<u8>),          // byts 0-23,24,25,26,27,31
+    Major3(Info, Vec<u8>),          // text 0-23,24,25,26,27,31
+    Major4(Info, Vec<Cbor>),        // list 0-23,24,25,26,27,31
+    Major5(Info, Vec<(Key, Cbor)>), // dict 0-23,24,25,26,27,31
+    Major6(Info, Tag),              // tags similar to major0
+    Major7(Info, SimpleValue),      // type refer SimpleValue
+}
+
+/// Dropped iteratively, nested lists and maps are moved out on to a heap
+/// allocated stack, so that dropping a deeply nested value, say one
+/// decoded by [Cbor::decode_iterative], does not exhaust the native stack.
+impl Drop for Cbor {
+    fn drop(&mut self) {
+        let mut stack: Vec<Cbor> = vec![];
+        take_nested(self, &mut stack);
+        while let Some(mut val) = stack.pop() {
+            take_nested(&mut val, &mut stack);
+        }
+    }
+}
+
+// move non-empty lists and maps within `val` on to `stack`, leaving `val`
+// with no nesting to drop.
+fn take_nested(val: &mut Cbor, stack: &mut Vec<Cbor>) {
+    let nested = |val: &Cbor| match val {
+        Cbor::Major4(_, list) => !list.is_empty(),
+        Cbor::Major5(_, dict) => !dict.is_empty(),
+        _ => false,
+    };
+    match val {
+        Cbor::Major4(_, list) => stack.extend(list.drain(..).filter(nested)),
+        Cbor::Major5(_, dict) => stack.extend(dict.drain(..).map(|(_, v)| v).filter(nested)),
+        _ => (),
+    }
+}
+
+/// Float encoding to use while converting data-model to Cbor.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FloatEncoding {
+    /// Always encode floats as canonical 64-bit big-endian, as required by
+    /// DAG-CBOR. A float value is encoded into the same bytes, hence the
+    /// same CID, irrespective of how it was computed or the platform. This
+    /// is the default.
+    F64Always,
+    /// Encode floats in the smallest of 16-bit, 32-bit or 64-bit form
+    /// that can represent the value without loss.
+    Smallest,
+}
+
+/// Policy for coercing map keys into [Key] while decoding.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MapKeyKind {
+    /// Only text keys are allowed, as required by DAG-CBOR, any other
+    /// key fails the decode.
+    StringOnly,
+    /// Keys are mapped by their major type, unsigned and negative
+    /// integers as [Key::Offset] and [Key::NegInt], byte-strings as
+    /// [Key::Bytes] and text-strings as [Key::Text]. This is the default.
+    Any,
+}
+
+impl Default for MapKeyKind {
+    fn default() -> MapKeyKind {
+        MapKeyKind::Any
+    }
+}
+
+/// Options for decoding cbor bytes, refer [Cbor::decode_with]. Default
+/// options are same as [DecodeOptions::lenient].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DecodeOptions {
+    /// Reject integers, lengths and tags that are not encoded in their
+    /// shortest form, maps whose keys are not in canonical order or
+    /// are duplicated, and links with trailing bytes after the cid.
+    pub canonical: bool,
+    /// Maximum nesting depth of lists and maps.
+    pub depth_limit: u32,
+    /// Policy for coercing map keys, refer [MapKeyKind].
+    pub keys: MapKeyKind,
+    /// Reject text-strings that are not valid UTF-8. Otherwise such text
+    /// is held as is, except for map keys that are held as [Key::Bytes].
+    pub strict_utf8: bool,
+    /// Reject 16-bit and 32-bit floats, allowing only 64-bit floats.
+    pub f64_only: bool,
+    /// Reject links, tag 42, whose byte-string is missing the leading
+    /// `0x00` multibase identity prefix.
+    pub link_prefix: bool,
+    /// Reject values outside the IPLD data-model, that is, undefined,
+    /// NaN and infinite floats, and tags other than 42.
+    pub ipld_only: bool,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> DecodeOptions {
+        DecodeOptions::lenient()
+    }
+}
+
+impl DecodeOptions {
+    /// Options for strict DAG-CBOR, canonical encoding, only text keys,
+    /// valid UTF-8 text, only 64-bit floats, `0x00` prefixed links and
+    /// only values in the IPLD data-model.
+    pub fn dag_cbor() -> DecodeOptions {
+        DecodeOptions {
+            canonical: true,
+            depth_limit: RECURSION_LIMIT,
+            keys: MapKeyKind::StringOnly,
+            strict_utf8: true,
+            f64_only: true,
+            link_prefix: true,
+            ipld_only: true,
+        }
+    }
+
+    /// Options for generic cbor, accept any well formed cbor within
+    /// [RECURSION_LIMIT].
+    pub fn lenient() -> DecodeOptions {
+        DecodeOptions {
+            canonical: false,
+            depth_limit: RECURSION_LIMIT,
+            keys: MapKeyKind::Any,
+            strict_utf8: false,
+            f64_only: false,
+            link_prefix: false,
+            ipld_only: false,
+        }
+    }
+
+    // check a freshly decoded value, its children are already checked.
+    #[cfg(feature = "std")]
+    fn check(&self, val: &Cbor) -> Result<()> {
+        use Cbor::*;
+
+        if self.canonical {
+            let head = match val {
+                Major0(info, num) | Major1(info, num) => Some((*info, *num)),
+                Major2(info, data) | Major3(info, data) => Some((*info, data.len() as u64)),
+                Major4(info, list) => Some((*info, list.len() as u64)),
+                Major5(info, dict) => Some((*info, dict.len() as u64)),
+                Major6(info, Tag::Link(_)) => Some((*info, TAG_IPLD_CID)),
+                Major6(info, Tag::UBignum(_)) => Some((*info, TAG_UBIGNUM)),
+                Major6(info, Tag::NBignum(_)) => Some((*info, TAG_NBIGNUM)),
+                Major7(..) => None,
+            };
+            match head {
+                Some((info, num)) if !is_shortest(info, num) => {
+                    err_at!(FailCbor, msg: "non-canonical header for major {}", val.major())?
+                }
+                _ => (),
+            }
+            if let Major5(_, dict) = val {
+                let mut iter = dict.iter().zip(dict.iter().skip(1));
+                if iter.any(|((a, _), (b, _))| a.cmp_canonical(b) != cmp::Ordering::Less) {
+                    err_at!(FailCbor, msg: "map keys not in canonical order")?
+                }
+            }
+        }
+
+        match val {
+            Major3(_, text) if self.strict_utf8 => {
+                err_at!(FailConvert, source: core::str::from_utf8(text))?;
+            }
+            Major7(_, SimpleValue::F16(_)) if self.f64_only => {
+                err_at!(FailCbor, msg: "16-bit float, expected 64-bit")?
+            }
+            Major7(_, SimpleValue::F32(_)) if self.f64_only => {
+                err_at!(FailCbor, msg: "32-bit float, expected 64-bit")?
+            }
+            _ => (),
+        }
+
+        if self.ipld_only {
+            let float = match val {
+                Major7(_, SimpleValue::F16(bits)) => Some(SimpleValue::f16_to_f64(*bits)),
+                Major7(_, SimpleValue::F32(val)) => Some(f64::from(*val)),
+                Major7(_, SimpleValue::F64(val)) => Some(*val),
+                _ => None,
+            };
+            match val {
+                Major7(_, SimpleValue::Undefined) => {
+                    err_at!(FailCbor, msg: "undefined is not in IPLD data-model")?
+                }
+                Major6(_, Tag::UBignum(_)) | Major6(_, Tag::NBignum(_)) => {
+                    err_at!(FailCbor, msg: "bignum tag, only tag 42 is allowed")?
+                }
+                _ => match float {
+                    Some(f) if !f.is_finite() => err_at!(FailCbor, msg: "non-finite float {}", f)?,
+                    _ => (),
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Options for encoding data-model into cbor bytes, refer
+/// [Node::encode_with]. Default options are permissive, map entries are
+/// encoded in iteration order, floats in their smallest form and
+/// non-finite floats and undefined are allowed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EncodeOptions {
+    /// Sort map entries in canonical order, by their encoded keys.
+    pub sort_keys: bool,
+    /// Form of encoded floats, refer [FloatEncoding].
+    pub float: FloatEncoding,
+    /// Reject non-finite floats, that is NaN and ±Infinity.
+    pub finite_floats: bool,
+    /// Reject values outside the IPLD data-model, refer
+    /// [Node::is_dag_cbor_valid].
+    pub ipld_only: bool,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> EncodeOptions {
+        EncodeOptions {
+            sort_keys: false,
+            float: FloatEncoding::Smallest,
+            finite_floats: false,
+            ipld_only: false,
+        }
+    }
+}
+
+impl EncodeOptions {
+    /// Options for DAG-CBOR, sorted keys, 64-bit floats and only values
+    /// in the IPLD data-model.
+    pub fn dag_cbor() -> EncodeOptions {
+        EncodeOptions {
+            sort_keys: true,
+            float: FloatEncoding::F64Always,
+            finite_floats: true,
+            ipld_only: true,
+        }
+    }
 }
 
 impl TryFrom<&dyn Node> for Cbor {
     type Error = Error;
 
     fn try_from(node: &dyn Node) -> Result<Cbor> {
+        Cbor::from_node(node, FloatEncoding::F64Always)
+    }
+}
+
+impl Cbor {
+    /// Convert data-model `node` to Cbor value, using `fenc` for
+    /// encoding floats.
+    pub fn from_node(node: &dyn Node, fenc: FloatEncoding) -> Result<Cbor> {
         use crate::ipld::kind::{Key, Kind::*};
         use Cbor::*;
 
         let val: Cbor = match node.to_kind() {
             Null => Cbor::try_from(SimpleValue::Null)?,
+            Undefined => Cbor::undefined(),
             Bool => match node.to_bool().unwrap() {
                 true => Cbor::try_from(SimpleValue::True)?,
                 false => Cbor::try_from(SimpleValue::False)?,
             },
             Integer => match node.to_integer().unwrap() {
+                // integers outside the range of 64-bit major0/major1
+                // are not supported, they are not encoded as bignums.
                 num if num >= 0 => {
-                    let num: u64 = err_at!(FailConvert, num.try_into())?;
+                    let num: u64 = err_at!(FailConvert, num.try_into(), "integer {}", num)?;
                     Major0(num.into(), num)
                 }
                 num => {
-                    let num: u64 = err_at!(FailConvert, u64::try_from(i128::abs(num)))? - 1;
+                    let val = -1 - num;
+                    let num: u64 = err_at!(FailConvert, val.try_into(), "integer {}", num)?;
                     Major1(num.into(), num)
                 }
             },
-            Float => Cbor::try_from(SimpleValue::F64(node.to_float().unwrap()))?,
+            Float => match (fenc, node.to_float().unwrap()) {
+                (FloatEncoding::F64Always, f) => Cbor::try_from(SimpleValue::F64(f))?,
+                (FloatEncoding::Smallest, f) => Cbor::try_from(SimpleValue::from_f64(f))?,
+            },
             Bytes => {
                 let byts = node.as_bytes().unwrap().to_vec();
                 let n: u64 = err_at!(FailConvert, byts.len().try_into())?;
                 Major2(n.into(), byts)
             }
             Text => {
-                let text = node.as_ffi_string().unwrap().as_bytes().to_vec();
+                let text = match node.as_text_bytes() {
+                    Some(text) => text.to_vec(),
+                    None => err_at!(FailConvert, msg: "text kind without text")?,
+                };
                 let n: u64 = err_at!(FailConvert, text.len().try_into())?;
                 Major3(n.into(), text)
             }
@@ -68,20 +341,23 @@ impl TryFrom<&dyn Node> for Cbor {
             List => {
                 let mut items = vec![];
                 for x in node.iter() {
-                    items.push(Cbor::try_from(x)?)
+                    items.push(Cbor::from_node(x, fenc)?)
                 }
                 let n: u64 = err_at!(FailConvert, items.len().try_into())?;
                 Major4(n.into(), items)
             }
             Map => {
-                let mut map: BTreeMap<String, Cbor> = BTreeMap::new();
+                let mut map: Vec<(Key, Cbor)> = vec![];
                 for (key, value) in node.iter_entries() {
+                    // offsets from the end only make sense for lists.
                     let key = match key {
-                        Key::Text(key) => Ok(key),
-                        _ => err_at!(FailConvert, msg: "invalid key type"),
+                        Key::NegOffset(off) => {
+                            err_at!(FailConvert, msg: "invalid map key {}", off)
+                        }
+                        key => Ok(key),
                     }?;
-                    let value = Cbor::try_from(value)?;
-                    map.insert(key, value);
+                    let value = Cbor::from_node(value, fenc)?;
+                    map.push((key, value));
                 }
                 let n: u64 = err_at!(FailConvert, map.len().try_into())?;
                 Major5(n.into(), map)
@@ -92,124 +368,970 @@ impl TryFrom<&dyn Node> for Cbor {
     }
 }
 
+impl<'a> From<&'a Key> for Cbor {
+    fn from(key: &'a Key) -> Cbor {
+        use Cbor::*;
+
+        match key {
+            Key::Bool(true) => Major7(Info::Tiny(20), SimpleValue::True),
+            Key::Bool(false) => Major7(Info::Tiny(21), SimpleValue::False),
+            Key::Offset(off) => {
+                let num = *off as u64;
+                Major0(num.into(), num)
+            }
+            Key::NegOffset(off) if *off < 0 => {
+                let num = (-1 - *off) as u64;
+                Major1(num.into(), num)
+            }
+            Key::NegOffset(off) => {
+                let num = *off as u64;
+                Major0(num.into(), num)
+            }
+            Key::NegInt(val) if *val < 0 => {
+                let num = (-1 - *val) as u64;
+                Major1(num.into(), num)
+            }
+            Key::NegInt(val) => {
+                let num = *val as u64;
+                Major0(num.into(), num)
+            }
+            Key::Text(text) => {
+                let n = text.len() as u64;
+                Major3(n.into(), text.as_bytes().to_vec())
+            }
+            Key::Bytes(byts) => {
+                let n = byts.len() as u64;
+                Major2(n.into(), byts.clone())
+            }
+            Key::Interned(text) => {
+                let n = text.len() as u64;
+                Major3(n.into(), text.as_bytes().to_vec())
+            }
+            Key::Keyable(val) => {
+                let text = val.to_string();
+                let n = text.len() as u64;
+                Major3(n.into(), text.into_bytes())
+            }
+        }
+    }
+}
+
 impl Cbor {
-    /// Serialize this cbor value.
-    pub fn encode(&self, buf: &mut Vec<u8>) -> Result<usize> {
-        self.do_encode(buf, 1)
+    /// Serialize this cbor value into `w`, streaming it value by value.
+    /// Return the number of bytes written.
+    #[cfg(feature = "std")]
+    pub fn encode<W: io::Write + ?Sized>(&self, w: &mut W) -> Result<usize> {
+        self.traced_encode(w, true)
     }
 
-    fn do_encode(&self, buf: &mut Vec<u8>, depth: u32) -> Result<usize> {
+    /// Same as [Cbor::encode], but map entries are sorted only if
+    /// `sort_keys` is set in `opts`, otherwise they are encoded in the
+    /// order they are held. Float options apply while converting from
+    /// data-model, refer [Node::encode_with].
+    #[cfg(feature = "std")]
+    pub fn encode_with<W>(&self, opts: &EncodeOptions, w: &mut W) -> Result<usize>
+    where
+        W: io::Write + ?Sized,
+    {
+        self.traced_encode(w, opts.sort_keys)
+    }
+
+    #[cfg(all(feature = "std", not(feature = "tracing")))]
+    fn traced_encode<W: io::Write + ?Sized>(&self, w: &mut W, sort: bool) -> Result<usize> {
+        self.do_encode(w, 1, sort)
+    }
+
+    // top-level encode, within a span recording the major type of the
+    // value and the number of bytes written.
+    #[cfg(all(feature = "std", feature = "tracing"))]
+    fn traced_encode<W: io::Write + ?Sized>(&self, w: &mut W, sort: bool) -> Result<usize> {
+        use tracing::field::Empty;
+
+        let major = u64::from(self.major());
+        let span = tracing::debug_span!("cbor_encode", major, len = Empty);
+        let _enter = span.enter();
+        let n = self.do_encode(w, 1, sort)?;
+        span.record("len", &(n as u64));
+        Ok(n)
+    }
+
+    #[cfg(feature = "std")]
+    fn do_encode<W>(&self, w: &mut W, depth: u32, sort: bool) -> Result<usize>
+    where
+        W: io::Write + ?Sized,
+    {
         if depth > RECURSION_LIMIT {
-            return err_at!(FailCbor, msg: "encode recursion limit exceeded");
+            return err_at!(DepthExceeded, msg: "encode recursion limit exceeded");
         }
 
         match self {
             Cbor::Major0(info, num) => {
-                let n = encode_hdr(Major::M0, *info, buf)?;
-                Ok(n + encode_addnl(*num, buf)?)
+                let n = encode_hdr(Major::M0, *info, w)?;
+                Ok(n + encode_addnl(*num, w)?)
             }
             Cbor::Major1(info, num) => {
-                let n = encode_hdr(Major::M1, *info, buf)?;
-                Ok(n + encode_addnl(*num - 1, buf)?)
+                let n = encode_hdr(Major::M1, *info, w)?;
+                Ok(n + encode_addnl(*num, w)?)
             }
             Cbor::Major2(info, byts) => {
-                let n = encode_hdr(Major::M2, *info, buf)?;
-                let m = encode_addnl(byts.len().try_into().unwrap(), buf)?;
-                buf.copy_from_slice(&byts);
+                let n = encode_hdr(Major::M2, *info, w)?;
+                let m = encode_addnl(byts.len().try_into().unwrap(), w)?;
+                err_at!(IOError, w.write_all(&byts))?;
                 Ok(n + m + byts.len())
             }
             Cbor::Major3(info, text) => {
-                let n = encode_hdr(Major::M3, *info, buf)?;
-                let m = encode_addnl(text.len().try_into().unwrap(), buf)?;
-                buf.copy_from_slice(text);
+                let n = encode_hdr(Major::M3, *info, w)?;
+                let m = encode_addnl(text.len().try_into().unwrap(), w)?;
+                err_at!(IOError, w.write_all(text))?;
                 Ok(n + m + text.len())
             }
             Cbor::Major4(info, list) => {
-                let n = encode_hdr(Major::M4, *info, buf)?;
-                let m = encode_addnl(list.len().try_into().unwrap(), buf)?;
+                let n = encode_hdr(Major::M4, *info, w)?;
+                let m = encode_addnl(list.len().try_into().unwrap(), w)?;
                 let mut acc = 0;
                 for x in list {
-                    acc += x.do_encode(buf, depth + 1)?;
+                    acc += x.do_encode(w, depth + 1, sort)?;
                 }
                 Ok(n + m + acc)
             }
             Cbor::Major5(info, dict) => {
-                let n = encode_hdr(Major::M5, *info, buf)?;
-                let m = encode_addnl(dict.len().try_into().unwrap(), buf)?;
+                let n = encode_hdr(Major::M5, *info, w)?;
+                let m = encode_addnl(dict.len().try_into().unwrap(), w)?;
+                // canonical encoding, map keys are sorted by their encoded
+                // bytes rather than by their string value.
+                let mut entries: Vec<(&Key, &Cbor)> = dict.iter().map(|(k, v)| (k, v)).collect();
+                if sort {
+                    entries.sort_by(|(a, _), (b, _)| a.cmp_canonical(b));
+                }
+
                 let mut acc = 0;
-                for (key, val) in dict.iter() {
-                    let info: Info = {
-                        let num: u64 = key.len().try_into().unwrap();
-                        num.into()
-                    };
-                    acc += Cbor::Major3(info, key.as_bytes().to_vec()).encode(buf)?;
-                    acc += val.do_encode(buf, depth + 1)?;
+                for (key, val) in entries.into_iter() {
+                    acc += Cbor::from(key).do_encode(w, depth + 1, sort)?;
+                    acc += val.do_encode(w, depth + 1, sort)?;
                 }
                 Ok(n + m + acc)
             }
             Cbor::Major6(info, tagg) => {
-                let n = encode_hdr(Major::M6, *info, buf)?;
-                let m = tagg.encode(buf)?;
+                let n = encode_hdr(Major::M6, *info, w)?;
+                let m = tagg.encode(w)?;
                 Ok(n + m)
             }
             Cbor::Major7(info, sval) => {
-                let n = encode_hdr(Major::M7, *info, buf)?;
-                let m = sval.encode(buf)?;
+                let n = encode_hdr(Major::M7, *info, w)?;
+                let m = sval.encode(w)?;
                 Ok(n + m)
             }
         }
     }
 
+    /// Return the major type of this value, 0..=7.
+    pub fn major(&self) -> u8 {
+        let major = match self {
+            Cbor::Major0(_, _) => Major::M0,
+            Cbor::Major1(_, _) => Major::M1,
+            Cbor::Major2(_, _) => Major::M2,
+            Cbor::Major3(_, _) => Major::M3,
+            Cbor::Major4(_, _) => Major::M4,
+            Cbor::Major5(_, _) => Major::M5,
+            Cbor::Major6(_, _) => Major::M6,
+            Cbor::Major7(_, _) => Major::M7,
+        };
+        major as u8
+    }
+
+    /// Return the argument in the header of this value, that is, the
+    /// number for integers, length for strings and containers and the tag
+    /// number for tags. For simple values only the one-byte simple value
+    /// has a numeric argument, floats and others return None.
+    pub fn arg(&self) -> Option<u64> {
+        match self {
+            Cbor::Major0(_, num) | Cbor::Major1(_, num) => Some(*num),
+            Cbor::Major2(_, byts) | Cbor::Major3(_, byts) => Some(byts.len() as u64),
+            Cbor::Major4(_, list) => Some(list.len() as u64),
+            Cbor::Major5(_, dict) => Some(dict.len() as u64),
+            Cbor::Major6(_, tag) => Some(u64::from(tag.clone())),
+            Cbor::Major7(_, SimpleValue::Reserved24(num)) => Some(*num as u64),
+            Cbor::Major7(_, _) => None,
+        }
+    }
+
+    /// Return cbor value for boolean `val`.
+    pub fn bool(val: bool) -> Cbor {
+        match val {
+            true => Cbor::Major7(Info::Tiny(20), SimpleValue::True),
+            false => Cbor::Major7(Info::Tiny(21), SimpleValue::False),
+        }
+    }
+
+    /// Return cbor value for null.
+    pub fn null() -> Cbor {
+        Cbor::Major7(Info::Tiny(22), SimpleValue::Null)
+    }
+
+    /// Return cbor value for undefined. Note that undefined is not part
+    /// of the IPLD data-model, it is converted to [Basic::Undefined] only
+    /// in lenient mode, refer [Basic::from_cbor].
+    pub fn undefined() -> Cbor {
+        Cbor::Major7(Info::Tiny(23), SimpleValue::Undefined)
+    }
+
+    /// Return the simple-value, if this is a major-7 value.
+    pub fn as_simple(&self) -> Option<&SimpleValue> {
+        match self {
+            Cbor::Major7(_, sval) => Some(sval),
+            _ => None,
+        }
+    }
+
+    /// Return whether this value is null.
+    pub fn is_null(&self) -> bool {
+        matches!(self.as_simple(), Some(SimpleValue::Null))
+    }
+
+    /// Return whether this value is undefined.
+    pub fn is_undefined(&self) -> bool {
+        matches!(self.as_simple(), Some(SimpleValue::Undefined))
+    }
+
+    /// Iterate over the items of a list value, without allocation.
+    /// Return None if this value is not a list.
+    pub fn array_iter(&self) -> Option<impl Iterator<Item = &Cbor>> {
+        match self {
+            Cbor::Major4(_, list) => Some(list.iter()),
+            _ => None,
+        }
+    }
+
+    /// Iterate over the (key, value) entries of a map value, in the order
+    /// they were decoded, without allocation. Return None if this value is
+    /// not a map.
+    pub fn map_iter(&self) -> Option<impl Iterator<Item = (&Key, &Cbor)>> {
+        match self {
+            Cbor::Major5(_, dict) => Some(dict.iter().map(|(k, v)| (k, v))),
+            _ => None,
+        }
+    }
+
+    /// Decode `text`, encoded in `base` format without the multibase
+    /// prefix, into bytes and deserialize them into Cbor value. Trailing
+    /// bytes after the cbor value are treated as error.
+    #[cfg(feature = "std")]
+    pub fn from_base(text: &str, base: Base) -> Result<Cbor> {
+        let data = err_at!(BadInput, base.decode(text))?;
+        let mut r: &[u8] = &data;
+        let val = Self::decode(&mut r)?;
+        match r.len() {
+            0 => Ok(val),
+            n => err_at!(FailCbor, msg: "{} trailing bytes after cbor value", n),
+        }
+    }
+
+    /// Serialize this cbor value and encode the bytes in `base` format,
+    /// without the multibase prefix.
+    #[cfg(feature = "std")]
+    pub fn to_base(&self, base: Base) -> Result<String> {
+        let mut buf = vec![];
+        self.encode(&mut buf)?;
+        Ok(base.encode(&buf))
+    }
+
     /// Deserialize a bytes from reader `r` to Cbor value.
+    #[cfg(feature = "std")]
     pub fn decode<R: io::Read>(r: &mut R) -> Result<Cbor> {
-        Self::do_decode(r, 1)
+        Self::traced_decode(r, &DecodeOptions::default())
+    }
+
+    /// Decode the cbor value at the start of `bytes`, return the value
+    /// along with the number of bytes consumed. Unlike
+    /// [decode_roundtrip], trailing bytes are not an error, use the count
+    /// to advance a cursor over a concatenation of cbor values.
+    #[cfg(feature = "std")]
+    pub fn decode_prefix(bytes: &[u8]) -> Result<(Cbor, usize)> {
+        let mut r = bytes;
+        let val = Self::decode(&mut r)?;
+        Ok((val, bytes.len() - r.len()))
     }
 
-    fn do_decode<R: io::Read>(r: &mut R, depth: u32) -> Result<Cbor> {
+    /// Same as [Cbor::decode], except that `bytes` are decoded, and
+    /// checked, as per `opts`. Trailing bytes after the value are ignored.
+    #[cfg(feature = "std")]
+    pub fn decode_with(opts: &DecodeOptions, bytes: &[u8]) -> Result<Cbor> {
+        let mut r = bytes;
+        Self::traced_decode(&mut r, opts)
+    }
+
+    /// Same as [Cbor::decode], except that map keys are coerced into
+    /// [Key] as per `keys` policy. Shorthand for [Cbor::decode_with].
+    #[cfg(feature = "std")]
+    pub fn decode_with_keys<R: io::Read>(r: &mut R, keys: MapKeyKind) -> Result<Cbor> {
+        let opts = DecodeOptions {
+            keys,
+            ..DecodeOptions::default()
+        };
+        Self::traced_decode(r, &opts)
+    }
+
+    #[cfg(all(feature = "std", not(feature = "tracing")))]
+    fn traced_decode<R: io::Read>(r: &mut R, opts: &DecodeOptions) -> Result<Cbor> {
+        Self::do_decode(r, 1, None, opts)
+    }
+
+    // top-level decode, within a span recording the number of bytes
+    // consumed and the major type of the decoded value.
+    #[cfg(all(feature = "std", feature = "tracing"))]
+    fn traced_decode<R: io::Read>(r: &mut R, opts: &DecodeOptions) -> Result<Cbor> {
+        use tracing::field::Empty;
+
+        let span = tracing::debug_span!("cbor_decode", len = Empty, major = Empty);
+        let _enter = span.enter();
+        let mut r = CountRead { r, n: 0 };
+        let val = Self::do_decode(&mut r, 1, None, opts)?;
+        span.record("len", &(r.n as u64));
+        span.record("major", &u64::from(val.major()));
+        Ok(val)
+    }
+
+    /// Same as [Cbor::decode], except that text keys in maps are
+    /// interned using `interner`, as [Key::Interned], so that repeated
+    /// keys share the same allocation.
+    #[cfg(feature = "std")]
+    pub fn decode_interned<R: io::Read>(r: &mut R, interner: &mut KeyInterner) -> Result<Cbor> {
+        Self::do_decode(r, 1, Some(interner), &DecodeOptions::default())
+    }
+
+    #[cfg(feature = "std")]
+    fn do_decode<R: io::Read>(
+        r: &mut R,
+        depth: u32,
+        mut interner: Option<&mut KeyInterner>,
+        opts: &DecodeOptions,
+    ) -> Result<Cbor> {
+        if depth > opts.depth_limit {
+            return err_at!(DepthExceeded, msg: "decode recursion limit exceeded");
+        }
+
+        let (major, info) = decode_hdr(r)?;
+        if opts.canonical && matches!(info, Info::Indefinite) {
+            err_at!(FailCbor, msg: "indefinite length for major {}", major as u8)?
+        }
+
+        let val = match major {
+            Major::M4 => {
+                let mut list: Vec<Cbor> = vec![];
+                let n = decode_addnl(info, r)?;
+                for _ in 0..n {
+                    list.push(Self::do_decode(
+                        r,
+                        depth + 1,
+                        interner.as_deref_mut(),
+                        opts,
+                    )?);
+                }
+                Cbor::Major4(info, list)
+            }
+            Major::M5 => {
+                // entries are kept in the order they appear in the source.
+                let mut dict: Vec<(Key, Cbor)> = vec![];
+                let n = decode_addnl(info, r)?;
+                for _ in 0..n {
+                    let key = Self::do_decode(r, depth + 1, None, opts)?;
+                    let key = extract_key(key, opts, interner.as_deref_mut())?;
+                    let val = Self::do_decode(r, depth + 1, interner.as_deref_mut(), opts)?;
+                    dict.push((key, val));
+                }
+                Cbor::Major5(info, dict)
+            }
+            major => Self::decode_scalar(major, info, r, depth, opts)?,
+        };
+        opts.check(&val)?;
+        Ok(val)
+    }
+
+    /// Same as [Cbor::decode], additionally record the byte span of every
+    /// decoded value within `buf`, refer [Spans]. Trailing bytes after the
+    /// value are ignored.
+    #[cfg(feature = "std")]
+    pub fn decode_spans(buf: &[u8]) -> Result<(Cbor, Spans)> {
+        let mut spans = Spans::default();
+        let mut r = buf;
+        let val = Self::do_decode_spans(buf, &mut r, 1, &mut vec![], &mut spans)?;
+        Ok((val, spans))
+    }
+
+    #[cfg(feature = "std")]
+    fn do_decode_spans(
+        buf: &[u8],
+        r: &mut &[u8],
+        depth: u32,
+        path: &mut Vec<Key>,
+        spans: &mut Spans,
+    ) -> Result<Cbor> {
         if depth > RECURSION_LIMIT {
-            return err_at!(FailCbor, msg: "decode recursion limt exceeded");
+            return err_at!(DepthExceeded, msg: "decode recursion limit exceeded");
         }
 
+        let start = buf.len() - r.len();
         let (major, info) = decode_hdr(r)?;
 
+        let val = match major {
+            Major::M4 => {
+                let mut list: Vec<Cbor> = vec![];
+                let n = decode_addnl(info, r)?;
+                for off in 0..n {
+                    path.push(Key::Offset(err_at!(FailConvert, usize::try_from(off))?));
+                    list.push(Self::do_decode_spans(buf, r, depth + 1, path, spans)?);
+                    path.pop();
+                }
+                Cbor::Major4(info, list)
+            }
+            Major::M5 => {
+                let mut dict: Vec<(Key, Cbor)> = vec![];
+                let n = decode_addnl(info, r)?;
+                for _ in 0..n {
+                    let key = extract_key(Self::decode(r)?, &DecodeOptions::default(), None)?;
+                    path.push(key.clone());
+                    let val = Self::do_decode_spans(buf, r, depth + 1, path, spans)?;
+                    path.pop();
+                    dict.push((key, val));
+                }
+                Cbor::Major5(info, dict)
+            }
+            major => Self::decode_scalar(major, info, r, depth, &DecodeOptions::default())?,
+        };
+
+        let end = buf.len() - r.len();
+        spans.spans.insert(path.clone(), (start, end));
+        Ok(val)
+    }
+
+    /// Same as [Cbor::decode], but use an explicit stack instead of
+    /// recursion for nested lists and maps, hence not subject to
+    /// [RECURSION_LIMIT]. Useful for deeply nested values that would
+    /// otherwise exhaust the native stack. Map keys and tag payloads are
+    /// scalars, their header is checked before decoding them, so that they
+    /// never nest.
+    #[cfg(feature = "std")]
+    pub fn decode_iterative<R: io::Read>(r: &mut R) -> Result<Cbor> {
+        Self::do_decode_iterative(r, &mut vec![], &mut vec![], None)
+    }
+
+    // `stack` and `scratch`, for reading text keys, are expected empty and
+    // may be reused across calls, refer [Decoder].
+    #[cfg(feature = "std")]
+    fn do_decode_iterative<R: io::Read>(
+        r: &mut R,
+        stack: &mut Vec<Frame>,
+        scratch: &mut Vec<u8>,
+        mut interner: Option<&mut KeyInterner>,
+    ) -> Result<Cbor> {
+        let opts = DecodeOptions::default();
+        loop {
+            let (major, info) = decode_hdr(r)?;
+            if let Some(Frame::Map(_, _, _, key @ None)) = stack.last_mut() {
+                let interner = interner.as_deref_mut();
+                *key = Some(Self::decode_key(major, info, r, &opts, scratch, interner)?);
+                continue;
+            }
+            let mut val = match major {
+                Major::M4 => match decode_addnl(info, r)? {
+                    0 => Cbor::Major4(info, vec![]),
+                    n => {
+                        stack.push(Frame::List(info, n, vec![]));
+                        continue;
+                    }
+                },
+                Major::M5 => match decode_addnl(info, r)? {
+                    0 => Cbor::Major5(info, vec![]),
+                    n => {
+                        stack.push(Frame::Map(info, n, vec![], None));
+                        continue;
+                    }
+                },
+                Major::M6 => Cbor::Major6(info, Tag::decode_flat(info, r, &opts)?),
+                major => Self::decode_scalar(major, info, r, 1, &opts)?,
+            };
+
+            // fold the decoded value into its parents, for as long as
+            // the parent containers are complete.
+            loop {
+                match stack.last_mut() {
+                    None => return Ok(val),
+                    Some(Frame::List(_, n, list)) => {
+                        list.push(val);
+                        *n -= 1;
+                        if *n > 0 {
+                            break;
+                        }
+                    }
+                    Some(Frame::Map(_, n, dict, key)) => {
+                        dict.push((key.take().unwrap(), val));
+                        *n -= 1;
+                        if *n > 0 {
+                            break;
+                        }
+                    }
+                }
+                val = match stack.pop().unwrap() {
+                    Frame::List(info, _, list) => Cbor::Major4(info, list),
+                    Frame::Map(info, _, dict, _) => Cbor::Major5(info, dict),
+                };
+            }
+        }
+    }
+
+    // decode a map key, only integers and strings make for a key, refer
+    // [extract_key], any other major type is rejected before decoding it.
+    // Definite length text is read into `scratch`, so that a key already
+    // in `interner` is decoded without allocation.
+    #[cfg(feature = "std")]
+    fn decode_key<R>(
+        major: Major,
+        info: Info,
+        r: &mut R,
+        opts: &DecodeOptions,
+        scratch: &mut Vec<u8>,
+        interner: Option<&mut KeyInterner>,
+    ) -> Result<Key>
+    where
+        R: io::Read,
+    {
+        match major {
+            Major::M3 if !matches!(info, Info::Indefinite) => {
+                let n: usize = err_at!(FailConvert, decode_addnl(info, r)?.try_into())?;
+                read_bytes_into(r, n, scratch)?;
+                text_key(scratch, opts, interner)
+            }
+            Major::M0 | Major::M1 | Major::M2 | Major::M3 => {
+                let val = Self::decode_scalar(major, info, r, 1, opts)?;
+                extract_key(val, opts, interner)
+            }
+            major => err_at!(FailCbor, msg: "invalid key, major {}", major as u8),
+        }
+    }
+
+    /// Decode `bytes` as a stream of events pushed into `visitor`, without
+    /// building the value tree, refer [CborVisitor]. Decoding is subject
+    /// to `opts`, same as [Cbor::decode_with]. Byte-strings and
+    /// text-strings are borrowed from `bytes`, except indefinite length
+    /// strings that are joined from their chunks. Text is passed as `&str`,
+    /// hence invalid UTF-8 fails the decode even without `strict_utf8`.
+    /// Trailing bytes after the value are ignored.
+    #[cfg(feature = "std")]
+    pub fn decode_events<V>(opts: &DecodeOptions, bytes: &[u8], visitor: &mut V) -> Result<()>
+    where
+        V: CborVisitor,
+    {
+        let mut r = bytes;
+        Self::do_decode_events(&mut r, 1, opts, visitor)
+    }
+
+    #[cfg(feature = "std")]
+    fn do_decode_events<V>(
+        r: &mut &[u8],
+        depth: u32,
+        opts: &DecodeOptions,
+        visitor: &mut V,
+    ) -> Result<()>
+    where
+        V: CborVisitor,
+    {
+        if depth > opts.depth_limit {
+            return err_at!(DepthExceeded, msg: "decode recursion limit exceeded");
+        }
+
+        let (major, info) = decode_hdr(r)?;
+        if opts.canonical && matches!(info, Info::Indefinite) {
+            err_at!(FailCbor, msg: "indefinite length for major {}", major as u8)?
+        }
+        // header of strings, lists and maps, checked before visiting.
+        let check_len = |num: u64| match opts.canonical && !is_shortest(info, num) {
+            true => err_at!(FailCbor, msg: "non-canonical header for major {}", major as u8),
+            false => Ok(()),
+        };
+
+        match major {
+            Major::M2 | Major::M3 if matches!(info, Info::Indefinite) => {
+                let data = decode_chunks(major, r, opts)?;
+                visit_string(major, &data, visitor)
+            }
+            Major::M2 | Major::M3 => {
+                let data = take_bytes(info, r)?;
+                check_len(data.len() as u64)?;
+                visit_string(major, data, visitor)
+            }
+            Major::M4 => {
+                let n = decode_addnl(info, r)?;
+                check_len(n)?;
+                visitor.on_array_start(n)?;
+                for _ in 0..n {
+                    Self::do_decode_events(r, depth + 1, opts, visitor)?;
+                }
+                visitor.on_array_end()
+            }
+            Major::M5 => {
+                let n = decode_addnl(info, r)?;
+                check_len(n)?;
+                visitor.on_map_start(n)?;
+                let mut prev: Option<Key> = None;
+                for _ in 0..n {
+                    let key = Self::do_decode(r, depth + 1, None, opts)?;
+                    let key = extract_key(key, opts, None)?;
+                    let less = |prev: &Key| prev.cmp_canonical(&key) == cmp::Ordering::Less;
+                    if opts.canonical && !prev.as_ref().map_or(true, less) {
+                        err_at!(FailCbor, msg: "map keys not in canonical order")?
+                    }
+                    visitor.on_map_key(&key)?;
+                    Self::do_decode_events(r, depth + 1, opts, visitor)?;
+                    prev = Some(key);
+                }
+                visitor.on_map_end()
+            }
+            major => {
+                let val = Self::decode_scalar(major, info, r, depth, opts)?;
+                opts.check(&val)?;
+                match &val {
+                    Cbor::Major0(_, num) => visitor.on_int((*num).into()),
+                    Cbor::Major1(_, num) => visitor.on_int(-1 - i128::from(*num)),
+                    Cbor::Major6(_, Tag::Link(cid)) => visitor.on_link(cid),
+                    Cbor::Major6(_, tag) => visitor.on_int(tag.to_integer(false).unwrap()?),
+                    Cbor::Major7(_, sval) => match *sval {
+                        SimpleValue::True => visitor.on_bool(true),
+                        SimpleValue::False => visitor.on_bool(false),
+                        SimpleValue::Null => visitor.on_null(),
+                        SimpleValue::Undefined => visitor.on_undefined(),
+                        SimpleValue::F16(val) => visitor.on_float(SimpleValue::f16_to_f64(val)),
+                        SimpleValue::F32(val) => visitor.on_float(val.into()),
+                        SimpleValue::F64(val) => visitor.on_float(val),
+                        _ => err_at!(FailCbor, msg: "unsupported simple value"),
+                    },
+                    _ => err_at!(Fatal, msg: "not a scalar major {}", major as u8),
+                }
+            }
+        }
+    }
+
+    // decode a value that is neither a list nor a map.
+    #[cfg(feature = "std")]
+    fn decode_scalar<R>(
+        major: Major,
+        info: Info,
+        r: &mut R,
+        depth: u32,
+        opts: &DecodeOptions,
+    ) -> Result<Cbor>
+    where
+        R: io::Read,
+    {
         let val = match major {
             Major::M0 => Cbor::Major0(info, decode_addnl(info, r)?),
             Major::M1 => Cbor::Major1(info, decode_addnl(info, r)?),
+            // indefinite length strings are held in their definite form.
+            Major::M2 if matches!(info, Info::Indefinite) => {
+                let data = decode_chunks(major, r, opts)?;
+                Cbor::Major2(Info::from(data.len() as u64), data)
+            }
+            Major::M3 if matches!(info, Info::Indefinite) => {
+                let data = decode_chunks(major, r, opts)?;
+                Cbor::Major3(Info::from(data.len() as u64), data)
+            }
             Major::M2 => {
-                let n: usize = decode_addnl(info, r)?.try_into().unwrap();
-                let mut data = vec![0; n];
-                err_at!(IOError, r.read(&mut data))?;
-                Cbor::Major2(info, data)
+                let n: usize = err_at!(FailConvert, decode_addnl(info, r)?.try_into())?;
+                Cbor::Major2(info, read_bytes(r, n)?)
             }
             Major::M3 => {
-                let n: usize = decode_addnl(info, r)?.try_into().unwrap();
-                let mut data = vec![0; n];
-                err_at!(IOError, r.read(&mut data))?;
-                Cbor::Major3(info, data)
+                let n: usize = err_at!(FailConvert, decode_addnl(info, r)?.try_into())?;
+                Cbor::Major3(info, read_bytes(r, n)?)
             }
+            Major::M4 | Major::M5 => err_at!(Fatal, msg: "not a scalar major {}", major as u8)?,
+            Major::M6 => Cbor::Major6(info, Tag::decode(info, r, depth, opts)?),
+            Major::M7 => Cbor::Major7(info, SimpleValue::decode(info, r)?),
+        };
+        Ok(val)
+    }
+}
+
+// reader counting the bytes consumed, for tracing spans.
+#[cfg(all(feature = "std", feature = "tracing"))]
+struct CountRead<'a, R> {
+    r: &'a mut R,
+    n: usize,
+}
+
+#[cfg(all(feature = "std", feature = "tracing"))]
+impl<'a, R: io::Read> io::Read for CountRead<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.r.read(buf)?;
+        self.n += n;
+        Ok(n)
+    }
+}
+
+// partially decoded list or map, while decoding iteratively, along with
+// the count of items left and for maps the key of the entry being decoded,
+// None until the key is decoded.
+#[cfg(feature = "std")]
+enum Frame {
+    List(Info, u64, Vec<Cbor>),
+    Map(Info, u64, Vec<(Key, Cbor)>, Option<Key>),
+}
+
+/// Decoder for repeated decodes, say, millions of small blocks in a hot
+/// loop. Decoding is iterative, refer [Cbor::decode_iterative], and text
+/// keys in maps are interned as [Key::Interned]. Between calls the
+/// decoder holds on to its stack of partially decoded containers, its
+/// scratch buffer for reading keys and its [KeyInterner], so that keys
+/// repeated across blocks are decoded without allocation. Decoder is
+/// reset at the start of every call, hence it is safe to reuse after an
+/// error. Interned keys are held for the lifetime of the decoder.
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub struct Decoder {
+    stack: Vec<Frame>,
+    scratch: Vec<u8>,
+    interner: KeyInterner,
+}
+
+#[cfg(feature = "std")]
+impl Decoder {
+    /// Create a new decoder.
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
+
+    /// Deserialize `bytes` to Cbor value, same as [Cbor::decode_iterative]
+    /// except for interned keys. Trailing bytes after the value are
+    /// ignored.
+    pub fn decode(&mut self, bytes: &[u8]) -> Result<Cbor> {
+        self.stack.clear();
+        let mut r = bytes;
+        let (stack, scratch) = (&mut self.stack, &mut self.scratch);
+        Cbor::do_decode_iterative(&mut r, stack, scratch, Some(&mut self.interner))
+    }
+
+    /// Return the interner holding the keys decoded so far.
+    pub fn interner(&self) -> &KeyInterner {
+        &self.interner
+    }
+}
+
+/// Callbacks for [Cbor::decode_events], called in the order in which
+/// values appear in the source, containers are bracketed by their start
+/// and end callbacks and map entries are preceded by `on_map_key`. All
+/// callbacks default to no-op, an error returned from a callback aborts
+/// the decode with that error.
+pub trait CborVisitor {
+    /// Called for major type 0 and major type 1 integers.
+    fn on_int(&mut self, _val: i128) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_bytes(&mut self, _val: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_text(&mut self, _val: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called before the `len` items of a list.
+    fn on_array_start(&mut self, _len: u64) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_array_end(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called before the `len` entries of a map.
+    fn on_map_start(&mut self, _len: u64) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called before the value of every map entry.
+    fn on_map_key(&mut self, _key: &Key) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_map_end(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_link(&mut self, _cid: &Cid) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_bool(&mut self, _val: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_null(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_undefined(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called for half, single and double precision floats, widened to
+    /// f64.
+    fn on_float(&mut self, _val: f64) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Byte spans, `(start, end)`, of decoded values within the source
+/// buffer, indexed by their path from the root value. Use this to slice
+/// out the original bytes of a sub-value without re-encoding it, refer
+/// [Cbor::decode_spans].
+#[derive(Default)]
+pub struct Spans {
+    spans: BTreeMap<Vec<Key>, (usize, usize)>,
+}
+
+impl Spans {
+    /// Return the span of value at `path`, an empty path refers to the
+    /// root value. For map entries, span covers the value and not the key.
+    pub fn span(&self, path: &[Key]) -> Option<(usize, usize)> {
+        self.spans.get(path).cloned()
+    }
+
+    /// Return the number of recorded spans.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Return whether there are no recorded spans.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+}
+
+/// Borrowed view of Cbor value, byte-strings and text-strings are borrowed
+/// from the input buffer instead of being copied. Use [CborRef::to_owned]
+/// to promote it to [Cbor].
+#[derive(Clone)]
+pub enum CborRef<'a> {
+    Major0(Info, u64),                     // uint 0-23,24,25,26,27
+    Major1(Info, u64),                     // nint 0-23,24,25,26,27
+    Major2(Info, &'a [u8]),                // byts 0-23,24,25,26,27,31
+    Major3(Info, &'a [u8]),                // text 0-23,24,25,26,27,31
+    Major4(Info, Vec<CborRef<'a>>),        // list 0-23,24,25,26,27,31
+    Major5(Info, Vec<(Key, CborRef<'a>)>), // dict 0-23,24,25,26,27,31
+    Major6(Info, Tag),                     // tags similar to major0
+    Major7(Info, SimpleValue),             // type refer SimpleValue
+}
+
+impl<'a> CborRef<'a> {
+    /// Deserialize a cbor value from `buf`, borrowing byte-strings and
+    /// text-strings from `buf`. Return the value along with the remaining
+    /// bytes.
+    #[cfg(feature = "std")]
+    pub fn decode(buf: &'a [u8]) -> Result<(CborRef<'a>, &'a [u8])> {
+        let mut r = buf;
+        let val = Self::do_decode(&mut r, 1)?;
+        Ok((val, r))
+    }
+
+    #[cfg(feature = "std")]
+    fn do_decode(r: &mut &'a [u8], depth: u32) -> Result<CborRef<'a>> {
+        if depth > RECURSION_LIMIT {
+            return err_at!(DepthExceeded, msg: "decode recursion limit exceeded");
+        }
+
+        let (major, info) = decode_hdr(r)?;
+
+        let val = match major {
+            Major::M0 => CborRef::Major0(info, decode_addnl(info, r)?),
+            Major::M1 => CborRef::Major1(info, decode_addnl(info, r)?),
+            Major::M2 => CborRef::Major2(info, take_bytes(info, r)?),
+            Major::M3 => CborRef::Major3(info, take_bytes(info, r)?),
             Major::M4 => {
-                let mut list: Vec<Cbor> = vec![];
+                let mut list: Vec<CborRef> = vec![];
                 let n = decode_addnl(info, r)?;
                 for _ in 0..n {
                     list.push(Self::do_decode(r, depth + 1)?);
                 }
-                Cbor::Major4(info, list)
+                CborRef::Major4(info, list)
             }
             Major::M5 => {
-                let mut dict: BTreeMap<String, Cbor> = BTreeMap::new();
+                let mut dict: Vec<(Key, CborRef)> = vec![];
                 let n = decode_addnl(info, r)?;
                 for _ in 0..n {
-                    let key = extract_key(Self::decode(r)?)?;
+                    let key = Self::do_decode(r, depth + 1)?.to_owned();
+                    let key = extract_key(key, &DecodeOptions::default(), None)?;
                     let val = Self::do_decode(r, depth + 1)?;
-                    dict.insert(key, val);
+                    dict.push((key, val));
                 }
-                Cbor::Major5(info, dict)
+                CborRef::Major5(info, dict)
             }
-            Major::M6 => Cbor::Major6(info, Tag::decode(info, r)?),
-            Major::M7 => Cbor::Major7(info, SimpleValue::decode(info, r)?),
+            Major::M6 => {
+                let tag = Tag::decode(info, r, depth, &DecodeOptions::default())?;
+                CborRef::Major6(info, tag)
+            }
+            Major::M7 => CborRef::Major7(info, SimpleValue::decode(info, r)?),
         };
         Ok(val)
     }
+
+    /// Promote this borrowed value to an owned [Cbor] value.
+    pub fn to_owned(&self) -> Cbor {
+        match self {
+            CborRef::Major0(info, num) => Cbor::Major0(*info, *num),
+            CborRef::Major1(info, num) => Cbor::Major1(*info, *num),
+            CborRef::Major2(info, byts) => Cbor::Major2(*info, byts.to_vec()),
+            CborRef::Major3(info, text) => Cbor::Major3(*info, text.to_vec()),
+            CborRef::Major4(info, list) => {
+                Cbor::Major4(*info, list.iter().map(|x| x.to_owned()).collect())
+            }
+            CborRef::Major5(info, dict) => {
+                let dict = dict.iter().map(|(k, v)| (k.clone(), v.to_owned()));
+                Cbor::Major5(*info, dict.collect())
+            }
+            CborRef::Major6(info, tag) => Cbor::Major6(*info, tag.clone()),
+            CborRef::Major7(info, sval) => Cbor::Major7(*info, *sval),
+        }
+    }
+}
+
+// borrow the byte-string or text-string, whose length is encoded in
+// the additional info, from `r`.
+#[cfg(feature = "std")]
+fn take_bytes<'a>(info: Info, r: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let n: usize = err_at!(FailConvert, decode_addnl(info, r)?.try_into())?;
+    if r.len() < n {
+        err_at!(
+            FailConvert,
+            msg: "unexpected end of input, string of {} bytes, only {} available",
+            n,
+            r.len()
+        )?
+    }
+    let (data, rest) = r.split_at(n);
+    *r = rest;
+    Ok(data)
+}
+
+// push byte-string or text-string `data` into `visitor`.
+#[cfg(feature = "std")]
+fn visit_string<V: CborVisitor>(major: Major, data: &[u8], visitor: &mut V) -> Result<()> {
+    match major {
+        Major::M3 => visitor.on_text(err_at!(FailConvert, core::str::from_utf8(data))?),
+        _ => visitor.on_bytes(data),
+    }
+}
+
+/// Decode canonical cbor `bytes` into data-model and encode it back. For
+/// canonical input the output must be same as input, and for any input
+/// that decodes, the output must round-trip to itself. Meant as entry
+/// point for fuzzing the decoder and the encoder.
+#[cfg(feature = "std")]
+pub fn decode_roundtrip(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut r: &[u8] = bytes;
+    let val = Cbor::decode(&mut r)?;
+    if !r.is_empty() {
+        err_at!(FailCbor, msg: "{} trailing bytes after cbor value", r.len())?
+    }
+    Basic::try_from(val)?.to_cbor_bytes()
 }
 
 /// 3-bit value for major-type.
@@ -246,7 +1368,7 @@ impl TryFrom<u8> for Major {
 }
 
 /// 5-bit value for additional info.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Info {
     Tiny(u8), // 0..=23
     U8,
@@ -280,6 +1402,17 @@ impl TryFrom<u8> for Info {
     }
 }
 
+// whether `info` is the shortest form for argument `num`.
+#[cfg(feature = "std")]
+fn is_shortest(info: Info, num: u64) -> bool {
+    match (Info::from(num), info) {
+        (Info::Tiny(a), Info::Tiny(b)) => a == b,
+        (Info::U8, Info::U8) | (Info::U16, Info::U16) => true,
+        (Info::U32, Info::U32) | (Info::U64, Info::U64) => true,
+        _ => false,
+    }
+}
+
 impl From<u64> for Info {
     fn from(num: u64) -> Info {
         match num {
@@ -292,7 +1425,20 @@ impl From<u64> for Info {
     }
 }
 
-fn encode_hdr(major: Major, info: Info, buf: &mut Vec<u8>) -> Result<usize> {
+/// Encode the header for `major` type, along with its argument `num`, say
+/// the length of a list or a map. Useful when the items are encoded
+/// separately.
+#[cfg(feature = "parallel")]
+pub(crate) fn encode_head<W>(major: Major, num: u64, w: &mut W) -> Result<usize>
+where
+    W: io::Write + ?Sized,
+{
+    let n = encode_hdr(major, num.into(), w)?;
+    Ok(n + encode_addnl(num, w)?)
+}
+
+#[cfg(feature = "std")]
+fn encode_hdr<W: io::Write + ?Sized>(major: Major, info: Info, w: &mut W) -> Result<usize> {
     let info = match info {
         Info::Tiny(val) if val <= 23 => val,
         Info::Tiny(val) => err_at!(FailCbor, msg: "{} > 23", val)?,
@@ -305,13 +1451,53 @@ fn encode_hdr(major: Major, info: Info, buf: &mut Vec<u8>) -> Result<usize> {
         Info::Reserved30 => 30,
         Info::Indefinite => 31,
     };
-    buf.push((major as u8) << 5 | info);
+    err_at!(IOError, w.write_all(&[(major as u8) << 5 | info]))?;
     Ok(1)
 }
 
+// fill `buf` from the reader, a short read is treated as truncated input.
+#[cfg(feature = "std")]
+fn read_exact<R: io::Read>(r: &mut R, buf: &mut [u8]) -> Result<()> {
+    match r.read_exact(buf) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+            err_at!(FailConvert, msg: "unexpected end of input, expected {} bytes", buf.len())
+        }
+        Err(err) => err_at!(IOError, Err(err)),
+    }
+}
+
+// read a string of `n` bytes, where `n` comes from the input. Buffer grows
+// only as the bytes are read, so that a bogus length fails as truncated
+// input instead of allocating upfront.
+#[cfg(feature = "std")]
+fn read_bytes<R: io::Read>(r: &mut R, n: usize) -> Result<Vec<u8>> {
+    let mut data = vec![];
+    read_bytes_into(r, n, &mut data)?;
+    Ok(data)
+}
+
+// same as read_bytes, but replace the content of `data`, reusing its
+// allocation.
+#[cfg(feature = "std")]
+fn read_bytes_into<R: io::Read>(r: &mut R, n: usize, data: &mut Vec<u8>) -> Result<()> {
+    data.clear();
+    err_at!(IOError, r.by_ref().take(n as u64).read_to_end(data))?;
+    if data.len() != n {
+        err_at!(
+            FailConvert,
+            msg: "unexpected end of input, expected {} bytes, got {}",
+            n,
+            data.len()
+        )?
+    }
+    Ok(())
+}
+
+#[cfg(feature = "std")]
 fn decode_hdr<R: io::Read>(r: &mut R) -> Result<(Major, Info)> {
     let mut scratch = [0_u8; 8];
-    err_at!(IOError, r.read(&mut scratch[..1]))?;
+    read_exact(r, &mut scratch[..1])?;
 
     let b = scratch[0];
 
@@ -320,20 +1506,21 @@ fn decode_hdr<R: io::Read>(r: &mut R) -> Result<(Major, Info)> {
     Ok((major.try_into()?, info.try_into()?))
 }
 
-fn encode_addnl(num: u64, buf: &mut Vec<u8>) -> Result<usize> {
+#[cfg(feature = "std")]
+fn encode_addnl<W: io::Write + ?Sized>(num: u64, w: &mut W) -> Result<usize> {
     let mut scratch = [0_u8; 8];
     let n = match num {
         0..=23 => 0,
         n if n <= (u8::MAX as u64) => {
-            scratch.copy_from_slice(&(n as u8).to_be_bytes());
+            scratch[..1].copy_from_slice(&(n as u8).to_be_bytes());
             1
         }
         n if n <= (u16::MAX as u64) => {
-            scratch.copy_from_slice(&(n as u16).to_be_bytes());
+            scratch[..2].copy_from_slice(&(n as u16).to_be_bytes());
             2
         }
         n if n <= (u32::MAX as u64) => {
-            scratch.copy_from_slice(&(n as u32).to_be_bytes());
+            scratch[..4].copy_from_slice(&(n as u32).to_be_bytes());
             4
         }
         n => {
@@ -341,28 +1528,63 @@ fn encode_addnl(num: u64, buf: &mut Vec<u8>) -> Result<usize> {
             8
         }
     };
-    buf.copy_from_slice(&scratch[..n]);
+    err_at!(IOError, w.write_all(&scratch[..n]))?;
     Ok(n)
 }
 
+// concatenated chunks of an indefinite length string, until break. As per
+// RFC 8949 every chunk must be a definite length string of the same major
+// type as the indefinite string, and every text chunk must be valid UTF-8
+// by itself.
+#[cfg(feature = "std")]
+fn decode_chunks<R>(major: Major, r: &mut R, opts: &DecodeOptions) -> Result<Vec<u8>>
+where
+    R: io::Read,
+{
+    let mut data = vec![];
+    loop {
+        match decode_hdr(r)? {
+            (Major::M7, Info::Indefinite) => break Ok(data),
+            (m, Info::Indefinite) if m as u8 == major as u8 => {
+                err_at!(FailCbor, msg: "nested indefinite chunk, major {}", major as u8)?
+            }
+            (m, info) if m as u8 == major as u8 => {
+                let n: usize = err_at!(FailConvert, decode_addnl(info, r)?.try_into())?;
+                let chunk = read_bytes(r, n)?;
+                if matches!(major, Major::M3) && opts.strict_utf8 {
+                    err_at!(FailConvert, source: core::str::from_utf8(&chunk))?;
+                }
+                data.extend_from_slice(&chunk);
+            }
+            (m, _) => err_at!(
+                FailCbor,
+                msg: "chunk of major {} in indefinite string of major {}",
+                m as u8,
+                major as u8
+            )?,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 fn decode_addnl<R: io::Read>(info: Info, r: &mut R) -> Result<u64> {
     let mut scratch = [0_u8; 8];
     let n = match info {
         Info::Tiny(num) => num as u64,
         Info::U8 => {
-            err_at!(IOError, r.read(&mut scratch[..1]))?;
+            read_exact(r, &mut scratch[..1])?;
             u8::from_be_bytes(scratch[..1].try_into().unwrap()) as u64
         }
         Info::U16 => {
-            err_at!(IOError, r.read(&mut scratch[..2]))?;
+            read_exact(r, &mut scratch[..2])?;
             u16::from_be_bytes(scratch[..2].try_into().unwrap()) as u64
         }
         Info::U32 => {
-            err_at!(IOError, r.read(&mut scratch[..4]))?;
+            read_exact(r, &mut scratch[..4])?;
             u32::from_be_bytes(scratch[..4].try_into().unwrap()) as u64
         }
         Info::U64 => {
-            err_at!(IOError, r.read(&mut scratch[..8]))?;
+            read_exact(r, &mut scratch[..8])?;
             u64::from_be_bytes(scratch[..8].try_into().unwrap()) as u64
         }
         _ => err_at!(FailCbor, msg: "no additional value")?,
@@ -370,46 +1592,140 @@ fn decode_addnl<R: io::Read>(info: Info, r: &mut R) -> Result<u64> {
     Ok(n)
 }
 
-#[derive(Clone)]
+/// Tagged values, bignums hold their magnitude as big-endian bytes, for
+/// negative bignum the value is `-1 - magnitude`.
+#[derive(Clone, Eq, PartialEq)]
 pub enum Tag {
-    Link(Cid), // TAG_IPLD_CID
+    Link(Cid),        // TAG_IPLD_CID
+    UBignum(Vec<u8>), // TAG_UBIGNUM
+    NBignum(Vec<u8>), // TAG_NBIGNUM
 }
 
 impl From<Tag> for u64 {
     fn from(tag: Tag) -> u64 {
         match tag {
             Tag::Link(_) => TAG_IPLD_CID,
+            Tag::UBignum(_) => TAG_UBIGNUM,
+            Tag::NBignum(_) => TAG_NBIGNUM,
         }
     }
 }
 
 impl Tag {
-    fn encode(&self, buf: &mut Vec<u8>) -> Result<usize> {
-        match self {
+    /// Return the integer value of bignum, `None` for other tags. Bignums
+    /// beyond the range of i128 fail with `FailConvert`, unless `saturate`
+    /// is true, in which case they are clamped to `i128::MAX` or
+    /// `i128::MIN`.
+    pub fn to_integer(&self, saturate: bool) -> Option<Result<i128>> {
+        let (negative, data) = match self {
+            Tag::Link(_) => return None,
+            Tag::UBignum(data) => (false, data),
+            Tag::NBignum(data) => (true, data),
+        };
+
+        let data: Vec<u8> = data.iter().copied().skip_while(|b| *b == 0).collect();
+        let mag = match data.len() {
+            n if n <= 16 => {
+                let mut scratch = [0_u8; 16];
+                scratch[16 - n..].copy_from_slice(&data);
+                Some(u128::from_be_bytes(scratch))
+            }
+            _ => None,
+        };
+        // for negative bignum, -1 - i128::MAX is i128::MIN.
+        let num = match mag.filter(|mag| *mag <= (i128::MAX as u128)) {
+            Some(mag) if negative => -1 - (mag as i128),
+            Some(mag) => mag as i128,
+            None if saturate && negative => i128::MIN,
+            None if saturate => i128::MAX,
+            None => {
+                let n = data.len();
+                return Some(err_at!(FailConvert, msg: "bignum of {} bytes beyond i128", n));
+            }
+        };
+        Some(Ok(num))
+    }
+
+    #[cfg(feature = "std")]
+    fn encode<W: io::Write + ?Sized>(&self, w: &mut W) -> Result<usize> {
+        let (tag, data) = match self {
+            // links are prefixed with multibase identity, 0x00.
             Tag::Link(cid) => {
-                buf.copy_from_slice(&TAG_IPLD_CID.to_be_bytes());
-                let n = {
-                    let data = cid.encode()?;
-                    let m: u64 = err_at!(FailCbor, data.len().try_into())?;
-                    Cbor::Major2(m.into(), data).encode(buf)?
-                };
-                Ok(1 + n)
+                let mut data = vec![0x00];
+                data.extend_from_slice(&cid.encode()?);
+                (TAG_IPLD_CID, data)
             }
+            Tag::UBignum(data) => (TAG_UBIGNUM, data.to_vec()),
+            Tag::NBignum(data) => (TAG_NBIGNUM, data.to_vec()),
+        };
+        let m = encode_addnl(tag, w)?;
+        let n = {
+            let m: u64 = err_at!(FailCbor, data.len().try_into())?;
+            Cbor::Major2(m.into(), data).do_encode(w, 1, true)?
+        };
+        Ok(m + n)
+    }
+
+    // decode the tag number and its payload, the payload is a level deeper
+    // than the tag at `depth`.
+    #[cfg(feature = "std")]
+    fn decode<R>(info: Info, r: &mut R, depth: u32, opts: &DecodeOptions) -> Result<Tag>
+    where
+        R: io::Read,
+    {
+        match decode_addnl(info, r)? {
+            num @ TAG_UBIGNUM | num @ TAG_NBIGNUM | num @ TAG_IPLD_CID => {
+                let val = Cbor::do_decode(r, depth + 1, None, opts)?;
+                Tag::from_payload(num, val, opts)
+            }
+            num => err_at!(FailCbor, msg: "invalid tag value {}", num),
         }
     }
 
-    fn decode<R: io::Read>(info: Info, r: &mut R) -> Result<Tag> {
+    // same as decode, but without recursion, payload of all known tags is
+    // a byte-string, any other major type is rejected before decoding it.
+    #[cfg(feature = "std")]
+    fn decode_flat<R>(info: Info, r: &mut R, opts: &DecodeOptions) -> Result<Tag>
+    where
+        R: io::Read,
+    {
         match decode_addnl(info, r)? {
-            42 => match Cbor::decode(r)? {
-                Cbor::Major2(_, bytes) => {
-                    let (cid, _) = Cid::decode(&bytes)?;
-                    Ok(Tag::Link(cid))
+            num @ TAG_UBIGNUM | num @ TAG_NBIGNUM | num @ TAG_IPLD_CID => match decode_hdr(r)? {
+                (Major::M2, info) => {
+                    let val = Cbor::decode_scalar(Major::M2, info, r, 1, opts)?;
+                    Tag::from_payload(num, val, opts)
                 }
-                _ => err_at!(FailCbor, msg: "invalid cid"),
+                (major, _) => err_at!(FailCbor, msg: "tag {} over major {}", num, major as u8),
             },
             num => err_at!(FailCbor, msg: "invalid tag value {}", num),
         }
     }
+
+    #[cfg(feature = "std")]
+    fn from_payload(num: u64, mut val: Cbor, opts: &DecodeOptions) -> Result<Tag> {
+        match (num, &mut val) {
+            (TAG_IPLD_CID, Cbor::Major2(_, bytes)) => {
+                // binary CID never starts with 0x00, hence unambiguous.
+                let data = match bytes.split_first() {
+                    Some((0x00, data)) => data,
+                    _ if opts.link_prefix => {
+                        err_at!(FailCbor, msg: "link without 0x00 multibase prefix")?
+                    }
+                    _ => bytes.as_slice(),
+                };
+                let (cid, rem) = Cid::decode(data)?;
+                if opts.canonical && !rem.is_empty() {
+                    err_at!(FailCbor, msg: "{} trailing bytes after cid", rem.len())?
+                }
+                Ok(Tag::Link(cid))
+            }
+            (TAG_IPLD_CID, _) => err_at!(FailCbor, msg: "invalid cid"),
+            (TAG_UBIGNUM, Cbor::Major2(_, bytes)) => Ok(Tag::UBignum(mem::take(bytes))),
+            (TAG_NBIGNUM, Cbor::Major2(_, bytes)) => Ok(Tag::NBignum(mem::take(bytes))),
+            (TAG_UBIGNUM, _) | (TAG_NBIGNUM, _) => err_at!(FailCbor, msg: "invalid bignum"),
+            (num, _) => err_at!(FailCbor, msg: "invalid tag value {}", num),
+        }
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -429,6 +1745,26 @@ pub enum SimpleValue {
            // 32..=255 on-byte simple-value unassigned
 }
 
+/// Floats are compared by their bit pattern, so that NaN equals itself
+/// and `-0.0` is not equal to `+0.0`.
+impl PartialEq for SimpleValue {
+    fn eq(&self, other: &SimpleValue) -> bool {
+        use SimpleValue::*;
+
+        match (self, other) {
+            (Unassigned, Unassigned) | (True, True) | (False, False) => true,
+            (Null, Null) | (Undefined, Undefined) | (Break, Break) => true,
+            (Reserved24(a), Reserved24(b)) => a == b,
+            (F16(a), F16(b)) => a == b,
+            (F32(a), F32(b)) => a.to_bits() == b.to_bits(),
+            (F64(a), F64(b)) => a.to_bits() == b.to_bits(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for SimpleValue {}
+
 impl TryFrom<SimpleValue> for Cbor {
     type Error = Error;
 
@@ -442,7 +1778,7 @@ impl TryFrom<SimpleValue> for Cbor {
             Null => Cbor::Major7(Info::Tiny(22), sval),
             Undefined => err_at!(FailConvert, msg: "simple-value-undefined")?,
             Reserved24(_) => err_at!(FailConvert, msg: "simple-value-unassigned1")?,
-            F16(_) => err_at!(FailConvert, msg: "simple-value-f16")?,
+            F16(_) => Cbor::Major7(Info::U16, sval),
             F32(_) => Cbor::Major7(Info::U32, sval),
             F64(_) => Cbor::Major7(Info::U64, sval),
             Break => err_at!(FailConvert, msg: "simple-value-break")?,
@@ -453,7 +1789,36 @@ impl TryFrom<SimpleValue> for Cbor {
 }
 
 impl SimpleValue {
-    fn encode(&self, buf: &mut Vec<u8>) -> Result<usize> {
+    /// Return the smallest float simple-value that can represent `val`
+    /// without loss.
+    pub fn from_f64(val: f64) -> SimpleValue {
+        let f = val as f32;
+        if (f as f64) != val {
+            return SimpleValue::F64(val);
+        }
+        match f32_to_f16(f) {
+            Some(h) => SimpleValue::F16(h),
+            None => SimpleValue::F32(f),
+        }
+    }
+
+    /// Return the half-precision value, in `bits`, as f64.
+    pub fn f16_to_f64(bits: u16) -> f64 {
+        let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+        let exp = ((bits >> 10) & 0x1f) as i32;
+        let mant = (bits & 0x3ff) as f64;
+        // 2^exp, for normal exponents, `powi` is not available in core.
+        let pow2 = |exp: i32| f64::from_bits(((exp + 1023) as u64) << 52);
+        match exp {
+            0 => sign * mant * pow2(-24),
+            0x1f if mant == 0.0 => sign * f64::INFINITY,
+            0x1f => f64::NAN,
+            exp => sign * (1024.0 + mant) * pow2(exp - 25),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn encode<W: io::Write + ?Sized>(&self, w: &mut W) -> Result<usize> {
         use SimpleValue::*;
 
         let mut scratch = [0_u8; 8];
@@ -464,11 +1829,11 @@ impl SimpleValue {
                 1
             }
             F16(f) => {
-                scratch.copy_from_slice(&f.to_be_bytes());
+                scratch[..2].copy_from_slice(&f.to_be_bytes());
                 2
             }
             F32(f) => {
-                scratch.copy_from_slice(&f.to_be_bytes());
+                scratch[..4].copy_from_slice(&f.to_be_bytes());
                 4
             }
             F64(f) => {
@@ -476,27 +1841,32 @@ impl SimpleValue {
                 8
             }
         };
-        buf.copy_from_slice(&scratch[..n]);
+        err_at!(IOError, w.write_all(&scratch[..n]))?;
         Ok(n)
     }
 
+    #[cfg(feature = "std")]
     fn decode<R: io::Read>(info: Info, r: &mut R) -> Result<SimpleValue> {
         let mut scratch = [0_u8; 8];
         let val = match info {
             Info::Tiny(20) => SimpleValue::True,
             Info::Tiny(21) => SimpleValue::False,
             Info::Tiny(22) => SimpleValue::Null,
-            Info::Tiny(23) => err_at!(FailCbor, msg: "simple-value-undefined")?,
+            Info::Tiny(23) => SimpleValue::Undefined,
             Info::Tiny(_) => err_at!(FailCbor, msg: "simple-value-unassigned")?,
             Info::U8 => err_at!(FailCbor, msg: "simple-value-unassigned1")?,
-            Info::U16 => err_at!(FailCbor, msg: "simple-value-f16")?,
+            Info::U16 => {
+                read_exact(r, &mut scratch[..2])?;
+                let val = u16::from_be_bytes(scratch[..2].try_into().unwrap());
+                SimpleValue::F16(val)
+            }
             Info::U32 => {
-                err_at!(IOError, r.read(&mut scratch[..4]))?;
+                read_exact(r, &mut scratch[..4])?;
                 let val = f32::from_be_bytes(scratch[..4].try_into().unwrap());
                 SimpleValue::F32(val)
             }
             Info::U64 => {
-                err_at!(IOError, r.read(&mut scratch[..8]))?;
+                read_exact(r, &mut scratch[..8])?;
                 let val = f64::from_be_bytes(scratch[..8].try_into().unwrap());
                 SimpleValue::F64(val)
             }
@@ -509,12 +1879,81 @@ impl SimpleValue {
     }
 }
 
-fn extract_key(val: Cbor) -> Result<String> {
-    match val {
-        Cbor::Major3(_, s) => {
-            let key = err_at!(FailConvert, std::str::from_utf8(&s))?;
-            Ok(key.to_string())
+// convert `f` to half-precision only if it can be done without loss.
+fn f32_to_f16(f: f32) -> Option<u16> {
+    let bits = f.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let mant = bits & 0x7f_ffff;
+
+    match exp {
+        0 if mant == 0 => Some(sign),
+        0 => None, // f32 sub-normals are too small for f16.
+        0xff if mant == 0 => Some(sign | 0x7c00),
+        0xff => None,
+        exp => match exp - 127 {
+            e @ -14..=15 if mant & 0x1fff == 0 => {
+                Some(sign | (((e + 15) as u16) << 10) | ((mant >> 13) as u16))
+            }
+            e @ -24..=-15 => {
+                let (full, shift) = (mant | 0x80_0000, (-e - 1) as u32);
+                match full & ((1 << shift) - 1) {
+                    0 => Some(sign | ((full >> shift) as u16)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+    }
+}
+
+// map keys are coerced as per `keys` policy in `opts`, with
+// [MapKeyKind::Any] it is upto the data-model whether to accept non-text
+// keys, refer [Basic::from_cbor]. Text keys are interned when `interner`
+// is supplied. Text keys that are not valid UTF-8 fail the decode only
+// with `strict_utf8`, otherwise they are held as [Key::Bytes], same as
+// byte-string keys.
+#[cfg(feature = "std")]
+fn extract_key(
+    mut val: Cbor,
+    opts: &DecodeOptions,
+    interner: Option<&mut KeyInterner>,
+) -> Result<Key> {
+    match &mut val {
+        Cbor::Major3(_, s) => text_key(s, opts, interner),
+        val if opts.keys == MapKeyKind::StringOnly => {
+            err_at!(FailCbor, msg: "non-text map key, major {}", val.major())
         }
+        Cbor::Major0(_, num) => {
+            let off: usize = err_at!(FailConvert, (*num).try_into())?;
+            Ok(Key::Offset(off))
+        }
+        Cbor::Major1(_, num) => {
+            let val: i64 = err_at!(FailConvert, (*num).try_into())?;
+            Ok(Key::NegInt(-1 - val))
+        }
+        Cbor::Major2(_, byts) => Ok(Key::Bytes(mem::take(byts))),
         _ => err_at!(FailCbor, msg: "invalid key"),
     }
 }
+
+// key for text-string `s`, refer [extract_key].
+#[cfg(feature = "std")]
+fn text_key(
+    s: &mut Vec<u8>,
+    opts: &DecodeOptions,
+    interner: Option<&mut KeyInterner>,
+) -> Result<Key> {
+    match core::str::from_utf8(s) {
+        Ok(key) => match interner {
+            Some(interner) => Ok(Key::Interned(interner.intern(key))),
+            None => Ok(Key::Text(key.to_string())),
+        },
+        Err(_) if !opts.strict_utf8 => Ok(Key::Bytes(mem::take(s))),
+        Err(err) => err_at!(FailConvert, source: Err(err)),
+    }
+}
+
+#[cfg(test)]
+#[path = "cbor_test.rs"]
+mod cbor_test;
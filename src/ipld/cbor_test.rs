@@ -0,0 +1,1032 @@
+use std::sync::Arc;
+
+use super::*;
+use crate::{ipld::kind::Basic, multicodec};
+
+fn make_cid() -> Cid {
+    Cid::new_v1(Base::Base32Lower, multicodec::DAG_CBOR.into(), b"cbor").unwrap()
+}
+
+#[test]
+fn test_cbor_base() {
+    let text = "a2617a0262616101";
+    let val = Cbor::from_base(text, Base::Base16Lower).unwrap();
+    match &val {
+        Cbor::Major5(_, dict) => {
+            assert_eq!(dict.len(), 2);
+            match &dict[1] {
+                (Key::Text(key), Cbor::Major0(_, 1)) if key == "aa" => (),
+                _ => panic!("expected integer 1 for key aa"),
+            }
+        }
+        _ => panic!("expected major5"),
+    }
+    assert_eq!(val.to_base(Base::Base16Lower).unwrap(), text);
+
+    let err = Cbor::from_base("0102", Base::Base16Lower).err().unwrap();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+
+    let err = Cbor::from_base("zz", Base::Base16Lower).err().unwrap();
+    assert!(matches!(err, Error::BadInput(..)), "{}", err);
+}
+
+#[test]
+fn test_float_encoding() {
+    let encode = |val: f64, fenc: FloatEncoding| {
+        let mut buf = vec![];
+        Cbor::from_node(&Basic::Float(val), fenc)
+            .unwrap()
+            .encode(&mut buf)
+            .unwrap();
+        buf
+    };
+
+    let refs: Vec<(f64, Vec<u8>)> = vec![
+        (1.0, vec![0xf9, 0x3c, 0x00]),
+        (0.0, vec![0xf9, 0x00, 0x00]),
+        (-2.0, vec![0xf9, 0xc0, 0x00]),
+        (65504.0, vec![0xf9, 0x7b, 0xff]),
+        (5.960464477539063e-8, vec![0xf9, 0x00, 0x01]),
+        (100000.0, vec![0xfa, 0x47, 0xc3, 0x50, 0x00]),
+        (
+            1.1,
+            vec![0xfb, 0x3f, 0xf1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a],
+        ),
+    ];
+    for (val, bytes) in refs.into_iter() {
+        assert_eq!(encode(val, FloatEncoding::Smallest), bytes, "{}", val);
+
+        let mut r: &[u8] = &bytes;
+        let node = Basic::from_cbor(Cbor::decode(&mut r).unwrap(), false).unwrap();
+        assert_eq!(node.to_float(), Some(val));
+
+        let mut r: &[u8] = &bytes;
+        let res = Basic::try_from(Cbor::decode(&mut r).unwrap());
+        assert_eq!(res.is_ok(), bytes.len() == 9, "{}", val);
+    }
+
+    let bytes = encode(1.0, FloatEncoding::F64Always);
+    assert_eq!(bytes, vec![0xfb, 0x3f, 0xf0, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn test_float_deterministic() {
+    let refv = vec![0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0];
+
+    let node = Basic::Float(1.5);
+    assert_eq!(node.to_dag_cbor_bytes().unwrap(), refv);
+    assert_eq!(node.encoded_size().unwrap(), 9);
+
+    // same bytes irrespective of how the value was computed.
+    let node = Basic::Float((3.0_f32 / 2.0) as f64);
+    assert_eq!(node.to_dag_cbor_bytes().unwrap(), refv);
+
+    let node = Basic::try_from(Cbor::from_base("fb3ff8000000000000", Base::Base16Lower).unwrap());
+    assert_eq!(node.unwrap().to_float(), Some(1.5));
+    let node = Basic::try_from(Cbor::from_base("f93e00", Base::Base16Lower).unwrap());
+    assert!(node.is_err());
+}
+
+#[test]
+fn test_decode_roundtrip() {
+    let refs: Vec<&str> = vec![
+        // empty map and empty list
+        "a0",
+        "80",
+        // nested lists
+        "818180",
+        "83 01 820203 8204820506",
+        // max u64 integer, and u16 boundary
+        "1b ffffffffffffffff",
+        "1a 00010000",
+        // 24 byte text
+        "7818 616161616161616161616161 616161616161616161616161",
+        // map with canonically sorted keys
+        "a2 617a02 62616101",
+    ];
+    for text in refs.into_iter() {
+        let text: String = text.split_whitespace().collect();
+        let bytes = Base::Base16Lower.decode(&text).unwrap();
+        assert_eq!(decode_roundtrip(&bytes).unwrap(), bytes, "{}", text);
+    }
+
+    // non-canonical inputs are re-encoded in canonical form.
+    let refs: Vec<(&str, &str)> =
+        vec![("1817", "17"), ("a2 62626201 617a02", "a2 617a02 62626201")];
+    for (input, output) in refs.into_iter() {
+        let input: String = input.split_whitespace().collect();
+        let output: String = output.split_whitespace().collect();
+        let bytes = Base::Base16Lower.decode(&input).unwrap();
+        let out = decode_roundtrip(&bytes).unwrap();
+        assert_eq!(Base::Base16Lower.encode(&out), output);
+        assert_eq!(decode_roundtrip(&out).unwrap(), out);
+    }
+
+    let err = decode_roundtrip(&[0x80, 0x80]).unwrap_err();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+
+    // inputs from fuzzing, length headers far beyond the input must fail
+    // as truncated input, without allocating for the claimed length.
+    let refs: Vec<&str> = vec![
+        "5b ffffffffffffffff",
+        "7b ffffffffffffffff 61",
+        "5f 5b ffffffffffffffff 00 ff",
+        "7f 7b ffffffffffffffff 61 ff",
+        "9b ffffffffffffffff 00",
+    ];
+    for text in refs.into_iter() {
+        let text: String = text.split_whitespace().collect();
+        let bytes = Base::Base16Lower.decode(&text).unwrap();
+        let err = decode_roundtrip(&bytes).unwrap_err();
+        assert!(matches!(err, Error::FailConvert(..)), "{} {}", text, err);
+    }
+}
+
+#[test]
+fn test_cbor_ref() {
+    let text: String = (0..1000).map(|_| 'x').collect();
+    let doc: Basic = vec![
+        ("text", Basic::from(text.as_str())),
+        ("n", Basic::Integer(1)),
+    ]
+    .into_iter()
+    .map(|(k, v)| (Key::Text(k.to_string()), v))
+    .collect();
+    let buf = doc.to_cbor_bytes().unwrap();
+
+    let (val, rest) = CborRef::decode(&buf).unwrap();
+    assert!(rest.is_empty());
+    let range = (buf.as_ptr() as usize)..(buf.as_ptr() as usize + buf.len());
+    match &val {
+        CborRef::Major5(_, dict) => match &dict[1] {
+            (Key::Text(key), CborRef::Major3(_, s)) if key == "text" => {
+                assert_eq!(*s, text.as_bytes());
+                assert!(
+                    range.contains(&(s.as_ptr() as usize)),
+                    "expected a borrowed slice"
+                );
+            }
+            _ => panic!("expected text for key text"),
+        },
+        _ => panic!("expected major5"),
+    }
+
+    let mut out = vec![];
+    val.to_owned().encode(&mut out).unwrap();
+    assert_eq!(out, buf);
+
+    let err = CborRef::decode(&buf[..buf.len() - 1]).err().unwrap();
+    assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+}
+
+#[test]
+fn test_cbor_major_arg() {
+    let refs: Vec<(&str, u8, Option<u64>)> = vec![
+        ("1903e8", 0, Some(1000)),
+        ("29", 1, Some(9)),
+        ("43010203", 2, Some(3)),
+        ("6461626364", 3, Some(4)),
+        ("820102", 4, Some(2)),
+        ("a1616101", 5, Some(1)),
+        ("f5", 7, None),
+        ("f6", 7, None),
+        ("fb3ff8000000000000", 7, None),
+    ];
+    for (text, major, arg) in refs.into_iter() {
+        let val = Cbor::from_base(text, Base::Base16Lower).unwrap();
+        assert_eq!(val.major(), major, "{}", text);
+        assert_eq!(val.arg(), arg, "{}", text);
+    }
+
+    let val = Cbor::Major6(Info::U8, Tag::Link(make_cid()));
+    assert_eq!(val.major(), 6);
+    assert_eq!(val.arg(), Some(TAG_IPLD_CID));
+
+    let val = Cbor::Major7(Info::U8, SimpleValue::Reserved24(100));
+    assert_eq!(val.major(), 7);
+    assert_eq!(val.arg(), Some(100));
+}
+
+#[test]
+fn test_cbor_iter() {
+    // [1, 2, 3]
+    let val = Cbor::from_base("83010203", Base::Base16Lower).unwrap();
+    let items: Vec<Option<u64>> = val.array_iter().unwrap().map(|x| x.arg()).collect();
+    assert_eq!(items, vec![Some(1), Some(2), Some(3)]);
+    assert!(val.map_iter().is_none());
+
+    // {"z": 2, "aa": 1}
+    let val = Cbor::from_base("a2617a0262616101", Base::Base16Lower).unwrap();
+    let entries: Vec<(String, Option<u64>)> = val
+        .map_iter()
+        .unwrap()
+        .map(|(k, v)| (k.to_string(), v.arg()))
+        .collect();
+    let refs = vec![
+        ("key-str-z".to_string(), Some(2)),
+        ("key-str-aa".to_string(), Some(1)),
+    ];
+    assert_eq!(entries, refs);
+    assert!(val.array_iter().is_none());
+
+    let val = Cbor::from_base("17", Base::Base16Lower).unwrap();
+    assert!(val.array_iter().is_none());
+    assert!(val.map_iter().is_none());
+}
+
+#[test]
+fn test_cbor_stream() {
+    let buf = make_records(100);
+    let doc = Basic::try_from(Cbor::decode(&mut buf.as_slice()).unwrap()).unwrap();
+
+    let mut out = io::Cursor::new(vec![]);
+    let n = doc.encode_dag_cbor(&mut out).unwrap();
+    assert_eq!(n, buf.len());
+    assert_eq!(out.into_inner(), doc.to_cbor_bytes().unwrap());
+
+    let val = Cbor::try_from(&doc as &dyn Node).unwrap();
+    let mut out = vec![];
+    let n = val.encode(&mut io::BufWriter::new(&mut out)).unwrap();
+    assert_eq!(n, buf.len());
+    assert_eq!(out, buf);
+
+    let doc = Basic::Float(f64::NAN);
+    assert!(doc.encode_dag_cbor(&mut Vec::<u8>::new()).is_err());
+}
+
+#[test]
+fn test_integer_boundary() {
+    let max = u64::MAX as i128;
+    let testcases = vec![
+        (0, "00", 0, 0),
+        (23, "17", 0, 23),
+        (24, "1818", 0, 24),
+        (-1, "20", 1, 0),
+        (-24, "37", 1, 23),
+        (-25, "3818", 1, 24),
+        (-256, "38ff", 1, 255),
+        (-257, "390100", 1, 256),
+        (max, "1bffffffffffffffff", 0, u64::MAX),
+        (-1 - max, "3bffffffffffffffff", 1, u64::MAX),
+    ];
+    for (num, text, major, arg) in testcases.into_iter() {
+        let val = Cbor::try_from(&Basic::Integer(num) as &dyn Node).unwrap();
+        assert_eq!(val.major(), major, "{}", num);
+        assert_eq!(val.arg(), Some(arg), "{}", num);
+        assert_eq!(val.to_base(Base::Base16Lower).unwrap(), text, "{}", num);
+
+        let val = Cbor::from_base(text, Base::Base16Lower).unwrap();
+        let node = Basic::try_from(val).unwrap();
+        assert_eq!(node.to_integer(), Some(num), "{}", text);
+    }
+
+    for num in [max + 1, -2 - max, i128::MAX, i128::MIN].iter() {
+        match Cbor::try_from(&Basic::Integer(*num) as &dyn Node) {
+            Err(Error::FailConvert(..)) => (),
+            Err(err) => panic!("unexpected error {} for {}", err, num),
+            Ok(_) => panic!("expected error for {}", num),
+        }
+    }
+}
+
+#[test]
+fn test_cbor_simple() {
+    let val = Cbor::bool(true);
+    assert_eq!(val.to_base(Base::Base16Lower).unwrap(), "f5");
+    assert!(matches!(val.as_simple(), Some(SimpleValue::True)));
+    let val = Cbor::bool(false);
+    assert_eq!(val.to_base(Base::Base16Lower).unwrap(), "f4");
+    assert!(matches!(val.as_simple(), Some(SimpleValue::False)));
+    assert!(!val.is_null() && !val.is_undefined());
+
+    let val = Cbor::null();
+    assert_eq!(val.to_base(Base::Base16Lower).unwrap(), "f6");
+    assert!(val.is_null() && !val.is_undefined());
+    assert!(Basic::try_from(val).unwrap().is_null());
+
+    let val = Cbor::undefined();
+    assert_eq!(val.to_base(Base::Base16Lower).unwrap(), "f7");
+    assert!(val.is_undefined() && !val.is_null());
+    assert!(Basic::try_from(val).is_err());
+
+    let val = Cbor::from_base("f6", Base::Base16Lower).unwrap();
+    assert!(val.is_null());
+    let val = Cbor::from_base("00", Base::Base16Lower).unwrap();
+    assert!(val.as_simple().is_none());
+    assert!(!val.is_null() && !val.is_undefined());
+}
+
+#[test]
+fn test_decode_spans() {
+    // [1, "hello", [2, 3]]
+    let buf = Base::Base16Lower.decode("83016568656c6c6f820203").unwrap();
+    let (val, spans) = Cbor::decode_spans(&buf).unwrap();
+    assert_eq!(
+        val.to_base(Base::Base16Lower).unwrap(),
+        "83016568656c6c6f820203"
+    );
+    assert_eq!(spans.len(), 6);
+
+    assert_eq!(spans.span(&[]), Some((0, buf.len())));
+    let (start, end) = spans.span(&[Key::Offset(1)]).unwrap();
+    assert_eq!((start, end), (2, 8));
+    let mut r = &buf[start..end];
+    let item = Basic::try_from(Cbor::decode(&mut r).unwrap()).unwrap();
+    assert_eq!(item.as_string().unwrap().unwrap(), "hello");
+
+    assert_eq!(spans.span(&[Key::Offset(2)]), Some((8, 11)));
+    assert_eq!(
+        spans.span(&[Key::Offset(2), Key::Offset(1)]),
+        Some((10, 11))
+    );
+    assert_eq!(spans.span(&[Key::Offset(3)]), None);
+
+    // {"a": [1]}
+    let buf = Base::Base16Lower.decode("a161618101").unwrap();
+    let (_, spans) = Cbor::decode_spans(&buf).unwrap();
+    let key = Key::Text("a".to_string());
+    assert_eq!(spans.span(&[key.clone()]), Some((3, 5)));
+    assert_eq!(spans.span(&[key, Key::Offset(0)]), Some((4, 5)));
+}
+
+fn make_records(n: usize) -> Vec<u8> {
+    let keys = ["id", "name", "email", "active", "score"];
+    let records: Basic = (0..n)
+        .map(|i| {
+            let values = vec![
+                Basic::Integer(i as i128),
+                Basic::from(format!("user-{}", i)),
+                Basic::from(format!("user-{}@example.com", i)),
+                Basic::Bool(i % 2 == 0),
+                Basic::Integer((i % 100) as i128),
+            ];
+            let iter = keys.iter().map(|k| Key::Text(k.to_string()));
+            iter.zip(values.into_iter()).collect::<Basic>()
+        })
+        .collect();
+    records.to_cbor_bytes().unwrap()
+}
+
+#[test]
+fn test_decode_interned() {
+    use crate::ipld::kind::cmp_node;
+
+    let buf = make_records(1000);
+
+    let mut interner = KeyInterner::new();
+    let val = Cbor::decode_interned(&mut buf.as_slice(), &mut interner).unwrap();
+    assert_eq!(interner.len(), 5);
+
+    let mut keys: Vec<Arc<str>> = vec![];
+    for record in val.array_iter().unwrap() {
+        for (key, _) in record.map_iter().unwrap() {
+            match key {
+                Key::Interned(key) if &**key == "email" => keys.push(Arc::clone(key)),
+                Key::Interned(_) => (),
+                key => panic!("expected interned key {}", key),
+            }
+        }
+    }
+    assert_eq!(keys.len(), 1000);
+    assert!(keys.iter().all(|key| Arc::ptr_eq(key, &keys[0])));
+
+    let interned = Basic::try_from(val).unwrap();
+    let plain = Basic::try_from(Cbor::decode(&mut buf.as_slice()).unwrap()).unwrap();
+    assert_eq!(cmp_node(&interned, &plain), std::cmp::Ordering::Equal);
+    assert_eq!(interned.to_cbor_bytes().unwrap(), buf);
+
+    let record = interned.get(&Key::Offset(7)).unwrap();
+    let name = record.get(&Key::Text("name".to_string())).unwrap();
+    assert_eq!(name.as_string().unwrap().unwrap(), "user-7");
+}
+
+#[test]
+fn test_decode_iterative() {
+    use crate::ipld::kind::cmp_node;
+
+    let buf = make_records(100);
+    let val = Cbor::decode_iterative(&mut buf.as_slice()).unwrap();
+    let mut out = vec![];
+    val.encode(&mut out).unwrap();
+    assert_eq!(out, buf);
+
+    let iterative = Basic::try_from(val).unwrap();
+    let recursive = Basic::try_from(Cbor::decode(&mut buf.as_slice()).unwrap()).unwrap();
+    assert_eq!(cmp_node(&iterative, &recursive), std::cmp::Ordering::Equal);
+
+    for text in ["80", "a0", "83010203", "a2617a0262616101", "a1616181a0"].iter() {
+        let buf = Base::Base16Lower.decode(text).unwrap();
+        let val = Cbor::decode_iterative(&mut buf.as_slice()).unwrap();
+        assert_eq!(val.to_base(Base::Base16Lower).unwrap(), *text);
+    }
+
+    // [[[ ... ]]] and {"a": {"a": ... }}, nested 100_000 deep.
+    let mut lists = vec![0x81_u8; 100_000];
+    lists.push(0x80);
+    let mut maps: Vec<u8> = [0xa1, 0x61, 0x61]
+        .iter()
+        .copied()
+        .cycle()
+        .take(3 * 100_000)
+        .collect();
+    maps.push(0xa0);
+
+    for buf in [lists, maps].iter() {
+        match Cbor::decode(&mut buf.as_slice()) {
+            Err(Error::DepthExceeded(..)) => (),
+            _ => panic!("expected depth-exceeded error"),
+        }
+        // decoded and dropped without recursion.
+        let val = Cbor::decode_iterative(&mut buf.as_slice()).unwrap();
+        let (mut item, mut depth) = (&val, 0);
+        loop {
+            item = match item {
+                Cbor::Major4(_, list) if !list.is_empty() => &list[0],
+                Cbor::Major5(_, dict) if !dict.is_empty() => &dict[0].1,
+                _ => break,
+            };
+            depth += 1;
+        }
+        assert_eq!(depth, 100_000);
+        drop(val);
+    }
+
+    // map keys and tag payloads are checked before decoding, a key or a
+    // tag payload nested 100_000 deep is rejected upfront.
+    for prefix in [vec![0xa1_u8], vec![0xd8, 0x2a]].iter() {
+        let mut buf = prefix.clone();
+        buf.extend_from_slice(&vec![0x81_u8; 100_000]);
+        buf.push(0x80);
+        let err = Cbor::decode_iterative(&mut buf.as_slice()).err().unwrap();
+        assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+    }
+}
+
+#[bench]
+fn bench_decode_records(b: &mut test::Bencher) {
+    let buf = make_records(100_000);
+    b.iter(|| Cbor::decode(&mut buf.as_slice()).unwrap());
+}
+
+#[bench]
+fn bench_decode_records_interned(b: &mut test::Bencher) {
+    let buf = make_records(100_000);
+    b.iter(|| {
+        let mut interner = KeyInterner::new();
+        Cbor::decode_interned(&mut buf.as_slice(), &mut interner).unwrap()
+    });
+}
+
+#[test]
+fn test_decode_truncated() {
+    // empty input, and a list header missing its 1-byte length.
+    for buf in [vec![], vec![0x98]].iter() {
+        let errs = vec![
+            Cbor::decode(&mut buf.as_slice()).err().unwrap(),
+            Cbor::decode_iterative(&mut buf.as_slice()).err().unwrap(),
+            Cbor::decode_spans(buf).err().unwrap(),
+            CborRef::decode(buf).err().unwrap(),
+        ];
+        for err in errs.into_iter() {
+            assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+            assert!(
+                err.to_string().contains("unexpected end of input"),
+                "{}",
+                err
+            );
+        }
+    }
+
+    // text-string, 16-bit integer and 64-bit float, missing the last byte.
+    for text in ["63616263", "1903e8", "fb3ff0000000000000"].iter() {
+        let buf = Base::Base16Lower.decode(text).unwrap();
+        let buf = &buf[..buf.len() - 1];
+        let err = Cbor::decode(&mut &buf[..]).err().unwrap();
+        assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+        assert!(
+            err.to_string().contains("unexpected end of input"),
+            "{}",
+            err
+        );
+    }
+
+    // byte-string and text-string headers claiming 2^64-1 bytes, must
+    // fail without allocating for the claimed length.
+    for text in ["5bffffffffffffffff00", "7bffffffffffffffff61"].iter() {
+        let buf = Base::Base16Lower.decode(text).unwrap();
+        let errs = vec![
+            Cbor::decode(&mut buf.as_slice()).err().unwrap(),
+            Cbor::decode_iterative(&mut buf.as_slice()).err().unwrap(),
+            Cbor::decode_spans(&buf).err().unwrap(),
+            CborRef::decode(&buf).err().unwrap(),
+        ];
+        for err in errs.into_iter() {
+            assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+            assert!(
+                err.to_string().contains("unexpected end of input"),
+                "{}",
+                err
+            );
+        }
+    }
+}
+
+#[test]
+fn test_map_key_kind() {
+    assert_eq!(MapKeyKind::default(), MapKeyKind::Any);
+
+    // {"a": 1, 2: 3}
+    let buf = Base::Base16Lower.decode("a26161010203").unwrap();
+
+    let val = Cbor::decode_with_keys(&mut buf.as_slice(), MapKeyKind::Any).unwrap();
+    let keys: Vec<Key> = val.map_iter().unwrap().map(|(k, _)| k.clone()).collect();
+    assert_eq!(keys, vec![Key::Text("a".to_string()), Key::Offset(2)]);
+    let val = Cbor::decode(&mut buf.as_slice()).unwrap();
+    assert_eq!(val.map_iter().unwrap().count(), 2);
+
+    let err = Cbor::decode_with_keys(&mut buf.as_slice(), MapKeyKind::StringOnly)
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+
+    // {"a": {-1: 3}}, nested non-text key.
+    let buf = Base::Base16Lower.decode("a16161a12003").unwrap();
+    let err = Cbor::decode_with_keys(&mut buf.as_slice(), MapKeyKind::StringOnly)
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+
+    let buf = Base::Base16Lower.decode("a2616101616202").unwrap();
+    let val = Cbor::decode_with_keys(&mut buf.as_slice(), MapKeyKind::StringOnly).unwrap();
+    assert_eq!(val.to_base(Base::Base16Lower).unwrap(), "a2616101616202");
+}
+
+#[test]
+fn test_decoder_reuse() {
+    let blocks: Vec<Vec<u8>> = (0..10_000)
+        .map(|i| {
+            let doc: Basic = vec![
+                (Key::Text("n".to_string()), Basic::Integer(i)),
+                (Key::Text("tags".to_string()), make_list_of(i as usize % 4)),
+            ]
+            .into_iter()
+            .collect();
+            doc.to_cbor_bytes().unwrap()
+        })
+        .collect();
+
+    let mut decoder = Decoder::new();
+    let mut first: Option<Arc<str>> = None;
+    for (i, block) in blocks.iter().enumerate() {
+        let val = decoder.decode(block).unwrap();
+        let refv = Cbor::decode(&mut block.as_slice()).unwrap();
+        assert!(val == refv, "block {}", i);
+
+        // keys are shared across blocks, instead of allocated per block.
+        match val.map_iter().unwrap().next() {
+            Some((Key::Interned(key), _)) => {
+                let shared = first.get_or_insert_with(|| Arc::clone(key));
+                assert!(Arc::ptr_eq(shared, key), "block {}", i);
+            }
+            _ => panic!("expected interned key in block {}", i),
+        }
+
+        // an error in between must not leak into next decode.
+        if i % 1000 == 0 {
+            assert!(decoder.decode(&block[..block.len() - 1]).is_err());
+        }
+    }
+    assert_eq!(decoder.interner().len(), 2);
+}
+
+fn make_list_of(n: usize) -> Basic {
+    (0..n).map(|i| Basic::from(format!("tag-{}", i))).collect()
+}
+
+#[test]
+fn test_invalid_text_key() {
+    // {"\xff\xfe": 1}, text key carrying invalid UTF-8.
+    let buf = Base::Base16Lower.decode("a162fffe01").unwrap();
+    let key = Key::Bytes(vec![0xff, 0xfe]);
+
+    let mut interner = KeyInterner::new();
+    let vals = vec![
+        Cbor::decode_with(&DecodeOptions::lenient(), &buf).unwrap(),
+        Cbor::decode_iterative(&mut buf.as_slice()).unwrap(),
+        Cbor::decode_interned(&mut buf.as_slice(), &mut interner).unwrap(),
+    ];
+    for val in vals.iter() {
+        let keys: Vec<Key> = val.map_iter().unwrap().map(|(k, _)| k.clone()).collect();
+        assert_eq!(keys, vec![key.clone()]);
+    }
+
+    // bytes key is accepted only by the lenient data-model.
+    let doc = Basic::from_cbor(vals[0].clone(), false).unwrap();
+    assert_eq!(doc.get(&key).unwrap().to_integer(), Some(1));
+    assert!(Basic::from_cbor(vals[0].clone(), true).is_err());
+
+    let opts = DecodeOptions {
+        strict_utf8: true,
+        ..DecodeOptions::lenient()
+    };
+    let err = Cbor::decode_with(&opts, &buf).err().unwrap();
+    assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+}
+
+#[test]
+fn test_decode_options() {
+    let (dag, lenient) = (DecodeOptions::dag_cbor(), DecodeOptions::lenient());
+    assert_eq!(DecodeOptions::default(), lenient);
+
+    // 1 encoded as 2-byte integer, 16-bit float, unsorted keys {"b":1,"a":2},
+    // duplicate keys {"a":1,"a":2}, invalid text, integer key, "a" with
+    // 1-byte length, list length with 1-byte length, undefined, NaN,
+    // +inf, -inf, unsigned and negative bignum.
+    let rejects = [
+        "1801",
+        "f93c00",
+        "a2616201616102",
+        "a2616101616102",
+        "62fffe",
+        "a10102",
+        "a178016101",
+        "980101",
+        "f7",
+        "fb7ff8000000000000",
+        "fb7ff0000000000000",
+        "fbfff0000000000000",
+        "c24101",
+        "c34101",
+    ];
+    for text in rejects.iter() {
+        let buf = Base::Base16Lower.decode(text).unwrap();
+        assert!(Cbor::decode_with(&dag, &buf).is_err(), "{}", text);
+        assert!(Cbor::decode_with(&lenient, &buf).is_ok(), "{}", text);
+    }
+
+    let buf = Base::Base16Lower.decode("a2616101616202").unwrap();
+    Cbor::decode_with(&dag, &buf).unwrap();
+    let buf = make_records(10);
+    let val = Cbor::decode_with(&dag, &buf).unwrap();
+    let mut out = vec![];
+    val.encode(&mut out).unwrap();
+    assert_eq!(out, buf);
+
+    // [[[1]]] within and beyond depth limit.
+    let buf = Base::Base16Lower.decode("81818101").unwrap();
+    let opts = DecodeOptions {
+        depth_limit: 3,
+        ..DecodeOptions::lenient()
+    };
+    Cbor::decode_with(&opts, &buf).unwrap();
+    let opts = DecodeOptions {
+        depth_limit: 2,
+        ..DecodeOptions::lenient()
+    };
+    let err = Cbor::decode_with(&opts, &buf).err().unwrap();
+    assert!(matches!(err, Error::DepthExceeded(..)), "{}", err);
+}
+
+#[test]
+fn test_nested_tags() {
+    // tag 42 wrapping a list of tag 42 wrapping a list, and so on, nested
+    // far beyond the depth limit, must fail instead of overflowing stack.
+    let buf: Vec<u8> = [0xd8, 0x2a, 0x81]
+        .iter()
+        .copied()
+        .cycle()
+        .take(3 * 100_000)
+        .collect();
+    let errs = vec![
+        Cbor::decode(&mut buf.as_slice()).err().unwrap(),
+        Cbor::decode_with(&DecodeOptions::dag_cbor(), &buf)
+            .err()
+            .unwrap(),
+        Cbor::decode_spans(&buf).err().unwrap(),
+        CborRef::decode(&buf).err().unwrap(),
+    ];
+    for err in errs.into_iter() {
+        assert!(matches!(err, Error::DepthExceeded(..)), "{}", err);
+    }
+    // without recursion, payload other than bytes is rejected upfront.
+    let err = Cbor::decode_iterative(&mut buf.as_slice()).err().unwrap();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+
+    // payload of a tag is a level deeper than the tag.
+    let buf = Base::Base16Lower.decode("c24101").unwrap();
+    let opts = DecodeOptions {
+        depth_limit: 2,
+        ..DecodeOptions::lenient()
+    };
+    Cbor::decode_with(&opts, &buf).unwrap();
+    let opts = DecodeOptions {
+        depth_limit: 1,
+        ..DecodeOptions::lenient()
+    };
+    let err = Cbor::decode_with(&opts, &buf).err().unwrap();
+    assert!(matches!(err, Error::DepthExceeded(..)), "{}", err);
+}
+
+#[test]
+fn test_decode_prefix() {
+    // 10, 1000, "ab", back to back.
+    let buf = Base::Base16Lower.decode("0a1903e8626162").unwrap();
+
+    let mut vals = vec![];
+    let mut off = 0;
+    while off < buf.len() {
+        let (val, n) = Cbor::decode_prefix(&buf[off..]).unwrap();
+        vals.push(val.to_base(Base::Base16Lower).unwrap());
+        off += n;
+    }
+    assert_eq!(vals, vec!["0a", "1903e8", "626162"]);
+    assert_eq!(off, buf.len());
+
+    let (val, n) = Cbor::decode_prefix(&buf).unwrap();
+    assert_eq!(val.arg(), Some(10));
+    assert_eq!(n, 1);
+
+    assert!(Cbor::decode_prefix(&[]).is_err());
+    assert!(Cbor::decode_prefix(&buf[1..2]).is_err());
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_spans() {
+    use std::{fmt, sync::Mutex};
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    // records span names, along with the fields recorded on them.
+    #[derive(Clone, Default)]
+    struct Collect {
+        spans: Arc<Mutex<Vec<(String, Vec<String>)>>>,
+    }
+
+    struct Fields(Vec<String>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Collect {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+            let mut fields = Fields(vec![]);
+            attrs.record(&mut fields);
+            let mut spans = self.spans.lock().unwrap();
+            spans.push((attrs.metadata().name().to_string(), fields.0));
+            span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+            let mut fields = Fields(vec![]);
+            values.record(&mut fields);
+            let mut spans = self.spans.lock().unwrap();
+            spans[(id.into_u64() - 1) as usize].1.extend(fields.0);
+        }
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &span::Id) {}
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    // {"a": [1, 2]}
+    let buf = Base::Base16Lower.decode("a16161820102").unwrap();
+
+    let collect = Collect::default();
+    tracing::subscriber::with_default(collect.clone(), || {
+        let val = Cbor::decode(&mut buf.as_slice()).unwrap();
+        let mut out = vec![];
+        val.encode(&mut out).unwrap();
+    });
+
+    let spans = collect.spans.lock().unwrap().clone();
+    let refs = vec![
+        (
+            "cbor_decode".to_string(),
+            vec!["len=6".to_string(), "major=5".to_string()],
+        ),
+        (
+            "cbor_encode".to_string(),
+            vec!["major=5".to_string(), "len=6".to_string()],
+        ),
+    ];
+    assert_eq!(spans, refs);
+}
+
+#[test]
+fn test_decode_events() {
+    // sums integers, and tracks the deepest nesting, without a tree.
+    #[derive(Default)]
+    struct Sum {
+        total: i128,
+        depth: usize,
+        max_depth: usize,
+        keys: Vec<Key>,
+        texts: Vec<String>,
+    }
+
+    impl CborVisitor for Sum {
+        fn on_int(&mut self, val: i128) -> Result<()> {
+            self.total += val;
+            Ok(())
+        }
+
+        fn on_array_start(&mut self, _len: u64) -> Result<()> {
+            self.depth += 1;
+            self.max_depth = cmp::max(self.depth, self.max_depth);
+            Ok(())
+        }
+
+        fn on_array_end(&mut self) -> Result<()> {
+            self.depth -= 1;
+            Ok(())
+        }
+
+        fn on_map_key(&mut self, key: &Key) -> Result<()> {
+            self.keys.push(key.clone());
+            Ok(())
+        }
+
+        fn on_text(&mut self, val: &str) -> Result<()> {
+            self.texts.push(val.to_string());
+            Ok(())
+        }
+    }
+    let (dag, lenient) = (DecodeOptions::dag_cbor(), DecodeOptions::lenient());
+
+    // [1, [2, [3, -10]], "x", {"k": 100}]
+    let buf = Base::Base16Lower
+        .decode("840182028203296178a1616b1864")
+        .unwrap();
+    let mut sum = Sum::default();
+    Cbor::decode_events(&dag, &buf, &mut sum).unwrap();
+    assert_eq!(sum.total, 96);
+    assert_eq!(sum.max_depth, 3);
+    assert_eq!(sum.depth, 0);
+    assert_eq!(sum.keys, vec![Key::Text("k".to_string())]);
+    assert_eq!(sum.texts, vec!["x".to_string()]);
+
+    // callback errors abort the decode.
+    struct Fail;
+    impl CborVisitor for Fail {
+        fn on_text(&mut self, _val: &str) -> Result<()> {
+            err_at!(Invalid, msg: "no text")
+        }
+    }
+    let err = Cbor::decode_events(&lenient, &buf, &mut Fail).unwrap_err();
+    assert!(matches!(err, Error::Invalid(..)), "{}", err);
+
+    assert!(Cbor::decode_events(&lenient, &buf[..4], &mut Sum::default()).is_err());
+
+    // options apply, {1: 2} with integer key, 1 as 2-byte integer and
+    // indefinite length text "ab".
+    let decode = |opts: &DecodeOptions, text: &str| {
+        let mut sum = Sum::default();
+        let buf = Base::Base16Lower.decode(text).unwrap();
+        Cbor::decode_events(opts, &buf, &mut sum).map(|_| sum)
+    };
+    let sum = decode(&lenient, "a10102").unwrap();
+    assert_eq!(sum.keys, vec![Key::Offset(1)]);
+    assert!(decode(&dag, "a10102").is_err());
+    assert_eq!(decode(&lenient, "1801").unwrap().total, 1);
+    assert!(decode(&dag, "1801").is_err());
+    let sum = decode(&lenient, "7f6161616262ff").unwrap();
+    assert_eq!(sum.texts, vec!["ab".to_string()]);
+    assert!(decode(&dag, "7f6161616262ff").is_err());
+
+    let opts = DecodeOptions {
+        depth_limit: 2,
+        ..lenient
+    };
+    let err = decode(&opts, "8181").err().unwrap();
+    assert!(matches!(err, Error::DepthExceeded(..)), "{}", err);
+}
+
+#[test]
+fn test_indefinite_strings() {
+    let decode = |text: &str| Cbor::from_base(text, Base::Base16Lower);
+
+    // (_ h'0102', h'03'), re-encoded in definite form.
+    let val = decode("5f4201024103ff").unwrap();
+    assert_eq!(val.to_base(Base::Base16Lower).unwrap(), "43010203");
+    // (_ "a", "b")
+    let val = decode("7f61616162ff").unwrap();
+    assert_eq!(val.to_base(Base::Base16Lower).unwrap(), "626162");
+    let val = decode("7fff").unwrap();
+    assert_eq!(val.to_base(Base::Base16Lower).unwrap(), "60");
+
+    // errant text chunk in byte string, and byte chunk in text string.
+    let err = decode("5f4201026161ff").err().unwrap();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+    let err = decode("7f61614162ff").err().unwrap();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+    // nested indefinite chunk, and missing break.
+    let err = decode("5f5f4101ffff").err().unwrap();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+    assert!(decode("5f4101").is_err());
+
+    let buf = Base::Base16Lower.decode("5f4101ff").unwrap();
+    let err = Cbor::decode_with(&DecodeOptions::dag_cbor(), &buf)
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+
+    // (_ "\xc3", "\xa9"), chunks split the UTF-8 sequence for "é", that
+    // is valid only when concatenated.
+    let buf = Base::Base16Lower.decode("7f61c361a9ff").unwrap();
+    let val = Cbor::decode_with(&DecodeOptions::lenient(), &buf).unwrap();
+    assert_eq!(val.to_base(Base::Base16Lower).unwrap(), "62c3a9");
+    let opts = DecodeOptions {
+        strict_utf8: true,
+        ..DecodeOptions::lenient()
+    };
+    let err = Cbor::decode_with(&opts, &buf).err().unwrap();
+    assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+
+    // chunk header claiming 2^64-1 bytes.
+    let err = decode("5f5bffffffffffffffff00ff").err().unwrap();
+    assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+}
+
+#[test]
+fn test_link_prefix() {
+    let cid = make_cid();
+    let data = cid.encode().unwrap();
+
+    let mut buf = vec![];
+    Cbor::Major6(Info::U8, Tag::Link(cid.clone()))
+        .encode(&mut buf)
+        .unwrap();
+    let mut refs = vec![0xd8, 0x2a, 0x58, (data.len() + 1) as u8, 0x00];
+    refs.extend_from_slice(&data);
+    assert_eq!(buf, refs);
+
+    for opts in [DecodeOptions::lenient(), DecodeOptions::dag_cbor()].iter() {
+        match &Cbor::decode_with(opts, &buf).unwrap() {
+            Cbor::Major6(_, Tag::Link(val)) => assert_eq!(val, &cid),
+            _ => unreachable!(),
+        }
+    }
+
+    // missing 0x00 prefix, accepted only in lenient mode.
+    let mut buf = vec![0xd8, 0x2a, 0x58, data.len() as u8];
+    buf.extend_from_slice(&data);
+    match &Cbor::decode_with(&DecodeOptions::lenient(), &buf).unwrap() {
+        Cbor::Major6(_, Tag::Link(val)) => assert_eq!(val, &cid),
+        _ => unreachable!(),
+    }
+    let err = Cbor::decode_with(&DecodeOptions::dag_cbor(), &buf)
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+
+    // trailing bytes after the cid, accepted only in lenient mode.
+    let mut buf = vec![0xd8, 0x2a, 0x58, (data.len() + 3) as u8, 0x00];
+    buf.extend_from_slice(&data);
+    buf.extend_from_slice(&[0xde, 0xad]);
+    match &Cbor::decode_with(&DecodeOptions::lenient(), &buf).unwrap() {
+        Cbor::Major6(_, Tag::Link(val)) => assert_eq!(val, &cid),
+        _ => unreachable!(),
+    }
+    let err = Cbor::decode_with(&DecodeOptions::dag_cbor(), &buf)
+        .err()
+        .unwrap();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+}
+
+#[test]
+fn test_cbor_eq() {
+    // {"a": [1, -2, h'ff', 1.5], "b": {"c": null}}
+    let text = "a2616184012142fff93e006162a16163f6";
+    let val = Cbor::from_base(text, Base::Base16Lower).unwrap();
+    let copy = val.clone();
+    assert!(val == copy);
+    assert_eq!(copy.to_base(Base::Base16Lower).unwrap(), text);
+
+    // same value, different map entry order and float width.
+    let other = Cbor::from_base("a26162a16163f6616184012142fff93e00", Base::Base16Lower).unwrap();
+    assert!(val != other);
+    let other = Cbor::from_base("a2616184012142fffa3fc000006162a16163f6", Base::Base16Lower);
+    assert!(val != other.unwrap());
+
+    let link = Cbor::Major6(Info::U8, Tag::Link(make_cid()));
+    assert!(link.clone() == link);
+
+    let nan = Cbor::try_from(SimpleValue::F64(std::f64::NAN)).unwrap();
+    assert!(nan.clone() == nan);
+    let (neg, pos) = (
+        Cbor::try_from(SimpleValue::F64(-0.0)).unwrap(),
+        Cbor::try_from(SimpleValue::F64(0.0)).unwrap(),
+    );
+    assert!(neg != pos);
+}
@@ -0,0 +1,101 @@
+//! Module implement structural diff between two IPLD documents.
+//!
+//! [diff] walks two nodes side by side and records the paths where they
+//! differ, useful for finding out why the CID of a block changed. Maps
+//! are compared by key and lists by index. When the kind of a value
+//! changes, the value is reported as modified without descending into
+//! it.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::cmp;
+
+use crate::ipld::kind::{cmp_node, Key, Kind, Node};
+
+/// Change between two documents, at `path` from their roots.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Change {
+    /// Value of `kind` is present only in the new document.
+    Added { path: Vec<Key>, kind: Kind },
+    /// Value of `kind` is present only in the old document.
+    Removed { path: Vec<Key>, kind: Kind },
+    /// Value at path differs, either in kind or, for scalars, in value.
+    Modified {
+        path: Vec<Key>,
+        old: Kind,
+        new: Kind,
+    },
+}
+
+impl Change {
+    /// Return the path of this change from the root.
+    pub fn to_path(&self) -> &[Key] {
+        match self {
+            Change::Added { path, .. } => path,
+            Change::Removed { path, .. } => path,
+            Change::Modified { path, .. } => path,
+        }
+    }
+}
+
+/// Compare document `a`, the old one, with document `b`, the new one,
+/// and return the changes in pre-order, keys of a map in key order.
+/// Identical documents return an empty list.
+pub fn diff(a: &dyn Node, b: &dyn Node) -> Vec<Change> {
+    let mut acc = vec![];
+    do_diff(a, b, &mut vec![], &mut acc);
+    acc
+}
+
+fn do_diff(a: &dyn Node, b: &dyn Node, path: &mut Vec<Key>, acc: &mut Vec<Change>) {
+    let (old, new) = (a.to_kind(), b.to_kind());
+    match (old, new) {
+        (Kind::Map, Kind::Map) => {
+            let am: BTreeMap<Key, &dyn Node> = a.iter_entries().collect();
+            let bm: BTreeMap<Key, &dyn Node> = b.iter_entries().collect();
+            let keys: BTreeSet<&Key> = am.keys().chain(bm.keys()).collect();
+            for key in keys.into_iter() {
+                path.push(key.clone());
+                do_diff_entry(am.get(key).copied(), bm.get(key).copied(), path, acc);
+                path.pop();
+            }
+        }
+        (Kind::List, Kind::List) => {
+            let (av, bv): (Vec<&dyn Node>, Vec<&dyn Node>) =
+                (a.iter().collect(), b.iter().collect());
+            for off in 0..cmp::max(av.len(), bv.len()) {
+                path.push(Key::Offset(off));
+                do_diff_entry(av.get(off).copied(), bv.get(off).copied(), path, acc);
+                path.pop();
+            }
+        }
+        (old, new) if old != new || cmp_node(a, b) != cmp::Ordering::Equal => {
+            let path = path.clone();
+            acc.push(Change::Modified { path, old, new })
+        }
+        _ => (),
+    }
+}
+
+fn do_diff_entry(
+    a: Option<&dyn Node>,
+    b: Option<&dyn Node>,
+    path: &mut Vec<Key>,
+    acc: &mut Vec<Change>,
+) {
+    match (a, b) {
+        (Some(a), Some(b)) => do_diff(a, b, path, acc),
+        (Some(a), None) => acc.push(Change::Removed {
+            path: path.clone(),
+            kind: a.to_kind(),
+        }),
+        (None, Some(b)) => acc.push(Change::Added {
+            path: path.clone(),
+            kind: b.to_kind(),
+        }),
+        (None, None) => (),
+    }
+}
+
+#[cfg(test)]
+#[path = "diff_test.rs"]
+mod diff_test;
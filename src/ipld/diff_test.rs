@@ -0,0 +1,115 @@
+use super::*;
+use crate::ipld::kind::Basic;
+
+fn make_map(entries: Vec<(&str, Basic)>) -> Basic {
+    entries
+        .into_iter()
+        .map(|(k, v)| (Key::Text(k.to_string()), v))
+        .collect()
+}
+
+fn text_path(items: &[&str]) -> Vec<Key> {
+    items.iter().map(|k| Key::Text(k.to_string())).collect()
+}
+
+#[test]
+fn test_diff() {
+    let list: Basic = vec![Basic::Integer(1), Basic::Integer(2)]
+        .into_iter()
+        .collect();
+    let old = make_map(vec![
+        ("name", Basic::from("alice")),
+        ("age", Basic::Integer(30)),
+        ("tags", list.clone()),
+    ]);
+    assert!(diff(&old, &old.clone()).is_empty());
+
+    // age changed value, email added.
+    let new = make_map(vec![
+        ("name", Basic::from("alice")),
+        ("age", Basic::Integer(31)),
+        ("email", Basic::from("alice@example.com")),
+        ("tags", list),
+    ]);
+    let changes = diff(&old, &new);
+    assert_eq!(
+        changes,
+        vec![
+            Change::Modified {
+                path: text_path(&["age"]),
+                old: Kind::Integer,
+                new: Kind::Integer,
+            },
+            Change::Added {
+                path: text_path(&["email"]),
+                kind: Kind::Text,
+            },
+        ]
+    );
+    assert_eq!(changes[1].to_path(), text_path(&["email"]).as_slice());
+
+    // the reverse removes email.
+    let changes = diff(&new, &old);
+    assert_eq!(
+        changes[1],
+        Change::Removed {
+            path: text_path(&["email"]),
+            kind: Kind::Text,
+        }
+    );
+}
+
+#[test]
+fn test_diff_nested() {
+    let list: Basic = vec![Basic::Integer(1), Basic::Integer(2)]
+        .into_iter()
+        .collect();
+    let old = make_map(vec![
+        ("tags", list),
+        ("meta", make_map(vec![("x", Basic::Null)])),
+    ]);
+
+    // list grows, a nested map changes kind of its value, and top-level
+    // value changes kind without descending into it.
+    let list: Basic = vec![Basic::Integer(1), Basic::Integer(5), Basic::Integer(3)]
+        .into_iter()
+        .collect();
+    let new = make_map(vec![
+        ("tags", list),
+        ("meta", make_map(vec![("x", Basic::Bool(true))])),
+    ]);
+    let changes = diff(&old, &new);
+    let mut path = text_path(&["tags"]);
+    path.push(Key::Offset(1));
+    let mut path2 = text_path(&["tags"]);
+    path2.push(Key::Offset(2));
+    assert_eq!(
+        changes,
+        vec![
+            Change::Modified {
+                path: text_path(&["meta", "x"]),
+                old: Kind::Null,
+                new: Kind::Bool,
+            },
+            Change::Modified {
+                path,
+                old: Kind::Integer,
+                new: Kind::Integer,
+            },
+            Change::Added {
+                path: path2,
+                kind: Kind::Integer,
+            },
+        ]
+    );
+
+    let changes = diff(&old, &Basic::Integer(1));
+    assert_eq!(
+        changes,
+        vec![Change::Modified {
+            path: vec![],
+            old: Kind::Map,
+            new: Kind::Integer,
+        }]
+    );
+}
@@ -0,0 +1,146 @@
+//! Module implement DAG-JSON encoding for the IPLD data-model.
+//!
+//! Links are encoded as `{"/": "<cid>"}` and bytes as
+//! `{"/": {"bytes": "<base64>"}}`, using standard base64 alphabet without
+//! padding. Map keys must be text and are written in sorted order of their
+//! UTF-8 bytes. Like DAG-CBOR, non-finite floats and undefined are not
+//! allowed. _Refer [dag-json] spec for details_.
+//!
+//! [dag-json]: https://ipld.io/specs/codecs/dag-json/spec/
+
+use multibase::Base;
+
+use std::io;
+
+use crate::{
+    ipld::kind::{Key, Kind, Node},
+    Error, Result,
+};
+
+/// Encode `node` in DAG-JSON format into `w`.
+pub fn encode_dag_json<W: io::Write>(node: &dyn Node, w: &mut W) -> Result<()> {
+    let mut buf = String::new();
+    do_encode(node, &mut buf)?;
+    err_at!(IOError, w.write_all(buf.as_bytes()))
+}
+
+/// Export `root` as newline delimited DAG-JSON into `w`. For list, every
+/// item is written as a line, and for map, every entry is written as a
+/// line holding a single-entry object. Scalars are written as a single
+/// line.
+pub fn export_ndjson<W: io::Write>(root: &dyn Node, w: &mut W) -> Result<()> {
+    let mut buf = String::new();
+    match root.to_kind() {
+        Kind::List => {
+            for item in root.iter() {
+                buf.clear();
+                do_encode(item, &mut buf)?;
+                buf.push('\n');
+                err_at!(IOError, w.write_all(buf.as_bytes()))?;
+            }
+        }
+        Kind::Map => {
+            for (key, value) in root.iter_entries() {
+                buf.clear();
+                buf.push('{');
+                encode_text(&to_text_key(&key)?, &mut buf);
+                buf.push(':');
+                do_encode(value, &mut buf)?;
+                buf.push_str("}\n");
+                err_at!(IOError, w.write_all(buf.as_bytes()))?;
+            }
+        }
+        _ => {
+            do_encode(root, &mut buf)?;
+            buf.push('\n');
+            err_at!(IOError, w.write_all(buf.as_bytes()))?;
+        }
+    }
+    Ok(())
+}
+
+fn do_encode(node: &dyn Node, buf: &mut String) -> Result<()> {
+    match node.to_kind() {
+        Kind::Null => buf.push_str("null"),
+        Kind::Undefined => err_at!(EncodeError, msg: "undefined not supported")?,
+        Kind::Bool => match node.to_bool().unwrap() {
+            true => buf.push_str("true"),
+            false => buf.push_str("false"),
+        },
+        Kind::Integer => buf.push_str(&node.to_integer().unwrap().to_string()),
+        Kind::Float => match node.to_float().unwrap() {
+            // debug format always carries a decimal point or an exponent,
+            // so that floats are distinguishable from integers.
+            val if val.is_finite() => buf.push_str(&format!("{:?}", val)),
+            val => err_at!(EncodeError, msg: "non-finite float {}", val)?,
+        },
+        Kind::Text => encode_text(node.as_string().unwrap()?, buf),
+        Kind::Bytes => {
+            let data = Base::Base64.encode(node.as_bytes().unwrap());
+            buf.push_str(&format!(r#"{{"/":{{"bytes":"{}"}}}}"#, data));
+        }
+        Kind::Link => {
+            let text = node.as_link().unwrap().to_text(None)?;
+            buf.push_str(&format!(r#"{{"/":"{}"}}"#, text));
+        }
+        Kind::List => {
+            buf.push('[');
+            for (i, item) in node.iter().enumerate() {
+                if i > 0 {
+                    buf.push(',');
+                }
+                do_encode(item, buf)?;
+            }
+            buf.push(']');
+        }
+        Kind::Map => {
+            let mut entries = vec![];
+            for (key, value) in node.iter_entries() {
+                entries.push((to_text_key(&key)?, value));
+            }
+            entries.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+
+            buf.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    buf.push(',');
+                }
+                encode_text(&key, buf);
+                buf.push(':');
+                do_encode(value, buf)?;
+            }
+            buf.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn to_text_key(key: &Key) -> Result<String> {
+    match key {
+        Key::Text(key) => Ok(key.clone()),
+        Key::Interned(key) => Ok(key.to_string()),
+        key => err_at!(EncodeError, msg: "invalid key type {}", key),
+    }
+}
+
+fn encode_text(text: &str, buf: &mut String) {
+    buf.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            '\u{08}' => buf.push_str("\\b"),
+            '\u{0c}' => buf.push_str("\\f"),
+            ch if (ch as u32) < 0x20 => buf.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => buf.push(ch),
+        }
+    }
+    buf.push('"');
+}
+
+#[cfg(test)]
+#[path = "json_test.rs"]
+mod json_test;
@@ -0,0 +1,90 @@
+use super::*;
+use crate::{cid::Cid, ipld::kind::Basic, multicodec};
+
+fn to_json(node: &dyn Node) -> String {
+    let mut buf = vec![];
+    encode_dag_json(node, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn test_encode_dag_json() {
+    let cid = Cid::new_v1(Base::Base32Lower, multicodec::DAG_CBOR.into(), b"json").unwrap();
+    let link = cid.to_text(None).unwrap();
+
+    let testcases = vec![
+        (Basic::Null, "null".to_string()),
+        (Basic::Bool(true), "true".to_string()),
+        (Basic::Integer(-10), "-10".to_string()),
+        (Basic::Float(1.0), "1.0".to_string()),
+        (Basic::Float(1.5), "1.5".to_string()),
+        (
+            Basic::from("a\"b\\c\n\u{1}"),
+            r#""a\"b\\c\n\u0001""#.to_string(),
+        ),
+        (
+            Basic::Bytes(vec![1, 2, 3].into()),
+            r#"{"/":{"bytes":"AQID"}}"#.to_string(),
+        ),
+        (Basic::Link(cid.into()), format!(r#"{{"/":"{}"}}"#, link)),
+    ];
+    for (node, refv) in testcases.into_iter() {
+        assert_eq!(to_json(&node), refv);
+    }
+
+    let doc: Basic = vec![
+        (Key::Text("zz".to_string()), Basic::Integer(1)),
+        (Key::Text("aaa".to_string()), Basic::Null),
+        (
+            Key::Text("b".to_string()),
+            vec![Basic::Integer(1), Basic::Integer(2)]
+                .into_iter()
+                .collect(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(to_json(&doc), r#"{"aaa":null,"b":[1,2],"zz":1}"#);
+
+    let mut buf = vec![];
+    assert!(encode_dag_json(&Basic::Float(f64::NAN), &mut buf).is_err());
+    assert!(encode_dag_json(&Basic::Undefined, &mut buf).is_err());
+    let doc: Basic = vec![(Key::Offset(1), Basic::Null)].into_iter().collect();
+    assert!(encode_dag_json(&doc, &mut buf).is_err());
+}
+
+#[test]
+fn test_export_ndjson() {
+    let map: Basic = vec![(Key::Text("a".to_string()), Basic::Integer(1))]
+        .into_iter()
+        .collect();
+    let doc: Basic = vec![Basic::from("one"), map, Basic::Bytes(vec![0xff].into())]
+        .into_iter()
+        .collect();
+
+    let mut buf = vec![];
+    export_ndjson(&doc, &mut buf).unwrap();
+    let text = String::from_utf8(buf).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines,
+        vec![r#""one""#, r#"{"a":1}"#, r#"{"/":{"bytes":"/w"}}"#]
+    );
+    for line in lines.into_iter() {
+        serde_json::from_str::<serde_json::Value>(line).unwrap();
+    }
+
+    let doc: Basic = vec![
+        (Key::Text("x".to_string()), Basic::Integer(1)),
+        (Key::Text("y".to_string()), Basic::Null),
+    ]
+    .into_iter()
+    .collect();
+    let mut buf = vec![];
+    export_ndjson(&doc, &mut buf).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), "{\"x\":1}\n{\"y\":null}\n");
+
+    let mut buf = vec![];
+    export_ndjson(&Basic::Integer(10), &mut buf).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), "10\n");
+}
@@ -1,19 +1,53 @@
 //! Module implement the data-model for IPLD.
 
-use std::{cmp, collections::BTreeMap, convert::TryFrom, fmt, result};
-
+use multibase::Base;
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+use core::{cmp, convert::TryFrom, fmt, iter::FromIterator, mem, result, str};
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "std")]
+use crate::ipld::cbor::EncodeOptions;
+#[cfg(not(feature = "std"))]
+use crate::no_std_prelude::*;
 use crate::{cid::Cid, ipld::cbor::Cbor, Error, Result};
 
+/// Maximum number of links followed while resolving a path, refer
+/// [Basic::resolve_path]. Bounds cycles across blocks, like A→B→A.
+pub const LINK_HOP_LIMIT: u32 = 1000;
+
 /// Every thing is a Node, almost.
-pub trait Node {
+///
+/// Nodes are `Send + Sync`, so that a decoded document can be shared
+/// across threads, say, for hashing sub-trees in parallel.
+pub trait Node: AsNode + Send + Sync {
     fn as_key(&self) -> Option<Key>;
 
     /// return the kind.
     fn to_kind(&self) -> Kind;
 
+    /// return the name of this node's kind in IPLD's type-system
+    /// vocabulary, refer [Kind::to_name]. Used in error messages.
+    fn kind_name(&self) -> &'static str {
+        self.to_kind().to_name()
+    }
+
     /// use key as index within the container.
     fn get(&self, key: &Key) -> Result<&dyn Node>;
 
+    /// same as [Node::get], but return a mutable reference to the value.
+    fn get_mut(&mut self, key: &Key) -> Result<&mut dyn Node>;
+
+    /// insert (key, value) within the container, in-place, using key as
+    /// the index. Return the old value if it already exists. For list,
+    /// key can either be an existing offset or the length of the list,
+    /// in which case value is appended.
+    fn insert(&mut self, key: Key, value: Box<dyn Node>) -> Result<Option<Box<dyn Node>>>;
+
     /// set (key, value) within the container, using key as the index,
     /// and, return a new mutated version of node.
     fn set(&self, key: &Key, value: Box<dyn Node>) -> Result<Box<dyn Node>>;
@@ -22,16 +56,61 @@ pub trait Node {
     /// return a new mutated version of node.
     fn delete(&self, key: &Key) -> Result<Box<dyn Node>>;
 
-    /// iterate over values.
+    /// iterate over values. Iteration order is deterministic, irrespective
+    /// of the backing container: lists yield values in index order, and
+    /// maps yield values in key order, refer [Key]'s `Ord`. Every
+    /// implementation must preserve this, [Node::walk] and [Node::links]
+    /// depend on it for stable output.
     fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &dyn Node> + 'a>;
 
     /// iterate over (key, value) entry, in case of list key is index
-    /// offset of value within the list.
+    /// offset of value within the list. Same order as [Node::iter].
     fn iter_entries<'a>(&'a self) -> Box<dyn Iterator<Item = (Key, &dyn Node)> + 'a>;
 
+    /// iterate over (key, value) entry in the order they are held by the
+    /// backing container. Same as [Node::iter_entries], except for the
+    /// insertion ordered map backing, refer [Basic::from_cbor_ordered],
+    /// that yields its entries in source order.
+    fn iter_source_entries<'a>(&'a self) -> Box<dyn Iterator<Item = (Key, &dyn Node)> + 'a> {
+        self.iter_entries()
+    }
+
     /// if kind is container type, return the length.
     fn len(&self) -> Option<usize>;
 
+    /// return the length, in bytes, of text and bytes kind, and `None`
+    /// for others. Unlike [Node::len], which counts the items of list and
+    /// map.
+    fn byte_len(&self) -> Option<usize> {
+        match self.to_kind() {
+            Kind::Text => self.as_text_bytes().map(|text| text.len()),
+            Kind::Bytes => self.as_bytes().map(|data| data.len()),
+            _ => None,
+        }
+    }
+
+    /// resolve each of `keys` against this node, same as calling
+    /// [Node::get] for each key. Fail on the first missing key.
+    fn get_many(&self, keys: &[Key]) -> Result<Vec<&dyn Node>> {
+        keys.iter().map(|key| self.get(key)).collect()
+    }
+
+    /// same as [Node::get_many], but missing keys resolve to `None`.
+    fn get_many_opt(&self, keys: &[Key]) -> Vec<Option<&dyn Node>> {
+        keys.iter().map(|key| self.get(key).ok()).collect()
+    }
+
+    /// return whether `key` indexes a value within the container.
+    fn contains_key(&self, key: &Key) -> bool {
+        self.get(key).is_ok()
+    }
+
+    /// return the keys within the container, in iteration order. For
+    /// list, keys are the offsets `0..len`. Empty for non-container kinds.
+    fn keys(&self) -> Vec<Key> {
+        self.iter_entries().map(|(key, _)| key).collect()
+    }
+
     fn is_null(&self) -> bool;
 
     fn to_bool(&self) -> Option<bool>;
@@ -40,13 +119,416 @@ pub trait Node {
 
     fn to_float(&self) -> Option<f64>;
 
+    /// return the value of float or integer kind as f64, for numeric
+    /// checks that don't care whether the number is an integer or float.
+    /// Integers that can't be represented exactly in f64 return `None`.
+    fn to_number(&self) -> Option<f64> {
+        match self.to_kind() {
+            Kind::Float => self.to_float(),
+            Kind::Integer => {
+                let num = self.to_integer()?;
+                let val = num as f64;
+                // i128::MAX as f64 rounds up to 2^127, out of i128 range.
+                match val < (i128::MAX as f64) && (val as i128) == num {
+                    true => Some(val),
+                    false => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
     fn as_string(&self) -> Option<Result<&str>>;
 
+    /// same as [Node::as_string], without the error detail, text that is
+    /// not valid UTF-8 returns `None`.
     fn as_ffi_string(&self) -> Option<&str>;
 
+    /// return the raw bytes of text kind, without validating them as
+    /// UTF-8, `None` for other kinds. Text decoded in lenient mode can
+    /// hold invalid UTF-8, use this instead of [Node::as_ffi_string] to
+    /// copy or compare such text.
+    fn as_text_bytes(&self) -> Option<&[u8]> {
+        self.as_ffi_string().map(|text| text.as_bytes())
+    }
+
     fn as_bytes(&self) -> Option<&[u8]>;
 
     fn as_link(&self) -> Option<&Cid>;
+
+    /// same as [Node::as_link], additionally, for bytes kind, try to
+    /// parse the bytes as binary CID. Some legacy data, like dag-pb,
+    /// encode links as plain byte-strings.
+    fn as_link_lenient(&self) -> Option<Cid> {
+        if let Some(cid) = self.as_link() {
+            return Some(cid.clone());
+        }
+        match Cid::decode(self.as_bytes()?) {
+            Ok((cid, rem)) if rem.is_empty() => Some(cid),
+            _ => None,
+        }
+    }
+
+    /// return a deep copy of this node as a trait object.
+    fn clone_box(&self) -> Box<dyn Node>;
+
+    /// walk the tree rooted at this node in pre-order, `visitor` is called
+    /// for every node along with its path from this node.
+    fn walk(&self, visitor: &mut dyn FnMut(&[Key], &dyn Node)) {
+        let mut path = vec![];
+        walk_node(self.as_node(), &mut path, visitor)
+    }
+
+    /// return all the links reachable from this node, along with its path
+    /// from this node. Links are returned in pre-order.
+    fn links(&self) -> Vec<(Vec<Key>, Cid)> {
+        let mut links = vec![];
+        self.walk(&mut |path, node| {
+            if let Some(cid) = node.as_link() {
+                links.push((path.to_vec(), cid.clone()))
+            }
+        });
+        links
+    }
+
+    /// call `f` for every link reachable from this node, in the same
+    /// order as [Node::links]. Unlike [Node::links], paths are not
+    /// tracked and nothing is collected, use this when only the CIDs
+    /// are needed, like while counting pins.
+    fn for_each_link(&self, f: &mut dyn FnMut(&Cid)) {
+        each_link(self.as_node(), f)
+    }
+
+    /// check whether this node, and all its children, are valid DAG-CBOR.
+    /// DAG-CBOR forbids non-finite floats, that is NaN and ±Infinity, and
+    /// undefined.
+    fn is_dag_cbor_valid(&self) -> Result<()> {
+        let mut res = Ok(());
+        self.walk(&mut |path, node| match (node.to_kind(), node.to_float()) {
+            _ if res.is_err() => (),
+            (Kind::Undefined, _) => {
+                res = err_at!(FailCbor, msg: "undefined at {:?}", path);
+            }
+            (Kind::Map, _) => {
+                if let Some((key, _)) = node.iter_entries().find(|(key, _)| !key.is_dag_cbor()) {
+                    res = err_at!(FailCbor, msg: "non-text map key {} at {:?}", key, path);
+                }
+            }
+            (_, Some(val)) if !val.is_finite() => {
+                res = err_at!(FailCbor, msg: "non-finite float {} at {:?}", val, path);
+            }
+            _ => (),
+        });
+        res
+    }
+
+    /// encode this node into cbor bytes, without checking for strict
+    /// DAG-CBOR compliance.
+    #[cfg(feature = "std")]
+    fn to_cbor_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        Cbor::try_from(self.as_node())?.encode(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// encode this node into canonical DAG-CBOR bytes. Fails if node is
+    /// not valid DAG-CBOR, refer [Node::is_dag_cbor_valid].
+    #[cfg(feature = "std")]
+    fn to_dag_cbor_bytes(&self) -> Result<Vec<u8>> {
+        self.is_dag_cbor_valid()?;
+        self.to_cbor_bytes()
+    }
+
+    /// same as [Node::to_dag_cbor_bytes], but stream the encoded bytes
+    /// into `w`, map keys are written in canonical order. Return the
+    /// number of bytes written.
+    #[cfg(feature = "std")]
+    fn encode_dag_cbor(&self, w: &mut dyn io::Write) -> Result<usize> {
+        self.is_dag_cbor_valid()?;
+        Cbor::try_from(self.as_node())?.encode(w)
+    }
+
+    /// encode this node into cbor bytes as per `opts`, streaming them
+    /// into `w`. Return the number of bytes written. Use
+    /// [EncodeOptions::dag_cbor] for canonical DAG-CBOR bytes.
+    #[cfg(feature = "std")]
+    fn encode_with(&self, opts: &EncodeOptions, w: &mut dyn io::Write) -> Result<usize> {
+        if opts.ipld_only {
+            self.is_dag_cbor_valid()?;
+        } else if opts.finite_floats {
+            let mut res = Ok(());
+            self.walk(&mut |path, node| match node.to_float() {
+                Some(val) if res.is_ok() && !val.is_finite() => {
+                    res = err_at!(FailCbor, msg: "non-finite float {} at {:?}", val, path);
+                }
+                _ => (),
+            });
+            res?;
+        }
+        Cbor::from_node(self.as_node(), opts.float)?.encode_with(opts, w)
+    }
+
+    /// return the exact length of cbor encoded bytes for this node, same
+    /// as `to_cbor_bytes()?.len()`, without actually encoding it.
+    fn encoded_size(&self) -> Result<usize> {
+        let node = self.as_node();
+        let n = match node.to_kind() {
+            Kind::Null | Kind::Undefined | Kind::Bool => 1,
+            Kind::Integer => match node.to_integer().unwrap() {
+                num if num >= 0 => hdr_size(err_at!(FailConvert, u64::try_from(num))?),
+                num => hdr_size(err_at!(FailConvert, u64::try_from(-1 - num))?),
+            },
+            Kind::Float => 9,
+            Kind::Text => match node.as_text_bytes() {
+                Some(text) => str_size(text.len()),
+                None => err_at!(FailConvert, msg: "text kind without text")?,
+            },
+            Kind::Bytes => str_size(node.as_bytes().unwrap().len()),
+            Kind::Link => {
+                // byte-string is prefixed with multibase identity, 0x00.
+                let data = node.as_link().unwrap().encode()?;
+                hdr_size(crate::ipld::cbor::TAG_IPLD_CID) + str_size(data.len() + 1)
+            }
+            Kind::List => {
+                let mut acc = hdr_size(node.len().unwrap() as u64);
+                for item in node.iter() {
+                    acc += item.encoded_size()?;
+                }
+                acc
+            }
+            Kind::Map => {
+                let mut acc = hdr_size(node.len().unwrap() as u64);
+                for (key, value) in node.iter_entries() {
+                    // keys are sized as they are encoded, refer
+                    // [Cbor::from_node].
+                    acc += match &key {
+                        Key::NegOffset(off) => {
+                            err_at!(FailConvert, msg: "invalid map key {}", off)?
+                        }
+                        key => match &Cbor::from(key) {
+                            Cbor::Major0(_, num) | Cbor::Major1(_, num) => hdr_size(*num),
+                            Cbor::Major2(_, data) | Cbor::Major3(_, data) => str_size(data.len()),
+                            // bool keys are encoded as simple values.
+                            _ => 1,
+                        },
+                    };
+                    acc += value.encoded_size()?;
+                }
+                acc
+            }
+        };
+        Ok(n)
+    }
+
+    /// materialize this node into an owned [Basic] tree, scalars are
+    /// cloned and containers are rebuilt by walking over their entries.
+    /// Lists are materialized as `Vec` and maps as `BTreeMap`.
+    fn to_basic(&self) -> Basic {
+        let node = self.as_node();
+        match node.to_kind() {
+            Kind::Null => Basic::Null,
+            Kind::Undefined => Basic::Undefined,
+            Kind::Bool => Basic::Bool(node.to_bool().unwrap()),
+            Kind::Integer => Basic::Integer(node.to_integer().unwrap()),
+            Kind::Float => Basic::Float(node.to_float().unwrap()),
+            Kind::Text => Basic::Text(node.as_text_bytes().unwrap().into()),
+            Kind::Bytes => Basic::Bytes(node.as_bytes().unwrap().into()),
+            Kind::Link => Basic::from(node.as_link().unwrap().clone()),
+            Kind::List => node.iter().map(|item| item.to_basic()).collect(),
+            Kind::Map => {
+                let iter = node.iter_entries();
+                iter.map(|(key, value)| (key, value.to_basic())).collect()
+            }
+        }
+    }
+
+    /// return a canonical copy of this node, to compare documents by their
+    /// canonical form. Maps are rebuilt in key order, refer
+    /// [Node::to_basic], after checking that the tree is valid DAG-CBOR,
+    /// refer [Node::is_dag_cbor_valid], and that no map, like one backed
+    /// by `Vec`, holds duplicate keys. Values are not coerced, that is,
+    /// integral floats remain floats.
+    fn normalize(&self) -> Result<Basic> {
+        self.is_dag_cbor_valid()?;
+        let mut res = Ok(());
+        self.walk(&mut |path, node| match node.to_kind() {
+            _ if res.is_err() => (),
+            Kind::Map => {
+                let mut keys = node.keys();
+                keys.sort();
+                if let Some(keys) = keys.windows(2).find(|keys| keys[0] == keys[1]) {
+                    res = err_at!(FailCbor, msg: "duplicate map key {} at {:?}", keys[0], path);
+                }
+            }
+            _ => (),
+        });
+        res?;
+        Ok(self.to_basic())
+    }
+
+    /// return the total number of nodes in the tree rooted at this node,
+    /// including this node.
+    fn node_count(&self) -> usize {
+        let mut n = 0;
+        self.walk(&mut |_, _| n += 1);
+        n
+    }
+}
+
+// Generic methods can't be part of Node trait without losing object safety,
+// hence implemented on the trait object, and on Basic.
+impl<'a> dyn Node + 'a {
+    /// Same as [Node::get], but `key` can be any type implementing
+    /// [ToKey], like `node.get_key("field")` or `node.get_key(3)`.
+    pub fn get_key<K: ToKey>(&self, key: K) -> Result<&dyn Node> {
+        self.get(&key.to_key())
+    }
+
+    /// Search the tree rooted at this node in pre-order, return the first
+    /// node accepted by `pred` along with its path from this node.
+    pub fn find<F>(&self, pred: F) -> Option<(Vec<Key>, &dyn Node)>
+    where
+        F: Fn(&dyn Node) -> bool,
+    {
+        let mut path = vec![];
+        let node = find_node(self, &mut path, &pred)?;
+        Some((path, node))
+    }
+}
+
+/// Convert a value into [Key], for indexing into list or map.
+pub trait ToKey {
+    fn to_key(&self) -> Key;
+}
+
+impl<'a> ToKey for &'a str {
+    fn to_key(&self) -> Key {
+        Key::Text(self.to_string())
+    }
+}
+
+impl ToKey for String {
+    fn to_key(&self) -> Key {
+        Key::Text(self.clone())
+    }
+}
+
+impl ToKey for usize {
+    fn to_key(&self) -> Key {
+        Key::Offset(*self)
+    }
+}
+
+/// Upcast a concrete [Node] type into a trait object. Automatically
+/// implemented for all types implementing [Node].
+pub trait AsNode {
+    fn as_node(&self) -> &dyn Node;
+}
+
+impl<T: Node> AsNode for T {
+    fn as_node(&self) -> &dyn Node {
+        self
+    }
+}
+
+impl Clone for Box<dyn Node> {
+    fn clone(&self) -> Self {
+        self.as_ref().clone_box()
+    }
+}
+
+fn walk_node(node: &dyn Node, path: &mut Vec<Key>, visitor: &mut dyn FnMut(&[Key], &dyn Node)) {
+    visitor(path, node);
+    for (key, value) in node.iter_entries() {
+        path.push(key);
+        walk_node(value, path, visitor);
+        path.pop();
+    }
+}
+
+fn each_link(node: &dyn Node, f: &mut dyn FnMut(&Cid)) {
+    match node.as_link() {
+        Some(cid) => f(cid),
+        None => node.iter().for_each(|value| each_link(value, f)),
+    }
+}
+
+// links within a container are replaced through the container, for the
+// leaf node can't be mutated as `dyn Node`.
+fn map_node_links(node: &mut dyn Node, f: &mut dyn FnMut(&Cid) -> Cid) {
+    let keys: Vec<Key> = node.iter_entries().map(|(key, _)| key).collect();
+    for key in keys.into_iter() {
+        let cid = match node.get(&key).map(|value| value.as_link()) {
+            Ok(Some(cid)) => f(cid),
+            Ok(None) => {
+                if let Ok(value) = node.get_mut(&key) {
+                    map_node_links(value, f)
+                }
+                continue;
+            }
+            Err(_) => continue,
+        };
+        node.insert(key, Box::new(Basic::from(cid))).ok();
+    }
+}
+
+// on success, `path` is left pointing to the node found.
+fn find_node<'a>(
+    node: &'a dyn Node,
+    path: &mut Vec<Key>,
+    pred: &dyn Fn(&dyn Node) -> bool,
+) -> Option<&'a dyn Node> {
+    if pred(node) {
+        return Some(node);
+    }
+    for (key, value) in node.iter_entries() {
+        path.push(key);
+        if let Some(node) = find_node(value, path, pred) {
+            return Some(node);
+        }
+        path.pop();
+    }
+    None
+}
+
+// entries of an insertion ordered map, sorted by key.
+fn sorted_entries(entries: &[(Key, Box<dyn Node>)]) -> Vec<&(Key, Box<dyn Node>)> {
+    let mut entries: Vec<&(Key, Box<dyn Node>)> = entries.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+// parse text key as offset into list, distinguishing negative offsets
+// and offsets beyond usize from non-numeric keys.
+fn parse_offset(key: &str) -> Result<usize> {
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+
+    match key.parse::<usize>() {
+        Ok(off) => Ok(off),
+        Err(_) if key.starts_with('-') && is_digits(&key[1..]) => {
+            err_at!(FailConvert, msg: "negative offset {} not allowed", key)
+        }
+        Err(_) if is_digits(key) => {
+            err_at!(FailConvert, msg: "offset {} exceeds {}", key, usize::MAX)
+        }
+        Err(_) => err_at!(FailConvert, msg: "offset {:?} is not a number", key),
+    }
+}
+
+// size of cbor header, along with the additional info for `num`.
+fn hdr_size(num: u64) -> usize {
+    match num {
+        0..=23 => 1,
+        n if n <= (u8::MAX as u64) => 2,
+        n if n <= (u16::MAX as u64) => 3,
+        n if n <= (u32::MAX as u64) => 5,
+        _ => 9,
+    }
+}
+
+// size of cbor encoded bytes/text of `n` length.
+fn str_size(n: usize) -> usize {
+    hdr_size(n as u64) + n
 }
 
 /// A subset of Basic, that can be used to index into recursive type, like
@@ -54,18 +536,29 @@ pub trait Node {
 pub enum Key {
     Bool(bool),
     Offset(usize),
+    /// Offset from the end of the list, `-1` being the last element.
+    NegOffset(isize),
+    /// Negative integer map key, as decoded from cbor major type 1.
+    /// Unlike [Key::NegOffset], it never indexes into a list.
+    NegInt(i64),
     Text(String),
+    /// Text key, shared across all its occurrences in a document, refer
+    /// [KeyInterner]. Compares equal to [Key::Text] with the same value.
+    Interned(Arc<str>),
     Bytes(Vec<u8>),
-    Keyable(Box<dyn ToString>),
+    Keyable(Box<dyn ToString + Send + Sync>),
 }
 
 impl Clone for Key {
     fn clone(&self) -> Self {
         match self {
-            val @ Key::Bool(_) => val.clone(),
-            val @ Key::Offset(_) => val.clone(),
-            val @ Key::Text(_) => val.clone(),
-            val @ Key::Bytes(_) => val.clone(),
+            Key::Bool(val) => Key::Bool(*val),
+            Key::Offset(val) => Key::Offset(*val),
+            Key::NegOffset(val) => Key::NegOffset(*val),
+            Key::NegInt(val) => Key::NegInt(*val),
+            Key::Text(val) => Key::Text(val.clone()),
+            Key::Interned(val) => Key::Interned(Arc::clone(val)),
+            Key::Bytes(val) => Key::Bytes(val.clone()),
             Key::Keyable(val) => Key::Keyable(Box::new(val.to_string())),
         }
     }
@@ -78,13 +571,22 @@ impl fmt::Display for Key {
         match self {
             Bool(val) => write!(f, "key-bool-{}", val),
             Offset(val) => write!(f, "key-off-{}", val),
+            NegOffset(val) => write!(f, "key-noff-{}", val),
+            NegInt(val) => write!(f, "key-nint-{}", val),
             Text(val) => write!(f, "key-str-{}", val),
+            Interned(val) => write!(f, "key-str-{}", val),
             Bytes(val) => write!(f, "key-bytes-{:?}", val), // TODO: use base64 encoding.
             Keyable(val) => write!(f, "key-key-{}", val.to_string()),
         }
     }
 }
 
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        <Key as fmt::Display>::fmt(self, f)
+    }
+}
+
 impl Eq for Key {}
 
 impl PartialEq for Key {
@@ -94,11 +596,16 @@ impl PartialEq for Key {
         match (self, other) {
             (Bool(a), Bool(b)) => a == b,
             (Offset(a), Offset(b)) => a == b,
+            (NegOffset(a), NegOffset(b)) => a == b,
+            (NegInt(a), NegInt(b)) => a == b,
             (Text(a), Text(b)) => a == b,
             (Bytes(a), Bytes(b)) => a == b,
             (Keyable(a), Keyable(b)) => a.to_string() == b.to_string(),
             (Text(a), Keyable(b)) => a == &b.to_string(),
             (Keyable(a), Text(b)) => &a.to_string() == b,
+            (Interned(a), Interned(b)) => a == b,
+            (Interned(a), Text(b)) | (Text(b), Interned(a)) => &**a == b.as_str(),
+            (Interned(a), Keyable(b)) | (Keyable(b), Interned(a)) => &**a == b.to_string(),
             (_, _) => false,
         }
     }
@@ -118,45 +625,124 @@ impl Ord for Key {
             (Bool(false), Bool(true)) => cmp::Ordering::Less,
             (Bool(true), Bool(false)) => cmp::Ordering::Greater,
             (Offset(a), Offset(b)) => a.cmp(b),
+            (NegOffset(a), NegOffset(b)) => a.cmp(b),
+            (NegInt(a), NegInt(b)) => a.cmp(b),
             (Text(a), Text(b)) => a.cmp(b),
             (Bytes(a), Bytes(b)) => a.cmp(b),
             (Keyable(a), Keyable(b)) => a.to_string().cmp(&b.to_string()),
             (Text(a), Keyable(b)) => a.cmp(&b.to_string()),
             (Keyable(a), Text(b)) => a.to_string().cmp(&b),
+            (Interned(a), Interned(b)) => a.cmp(b),
+            (Interned(a), Text(b)) => (**a).cmp(b.as_str()),
+            (Text(a), Interned(b)) => a.as_str().cmp(&**b),
+            (Interned(a), Keyable(b)) => (**a).cmp(b.to_string().as_str()),
+            (Keyable(a), Interned(b)) => a.to_string().as_str().cmp(&**b),
             (a, b) => a.to_variant().cmp(&b.to_variant()),
         }
     }
 }
 
 impl Key {
+    /// Compare keys as per DAG-CBOR canonical ordering, that is, by the
+    /// length of the encoded key and then by lexical order of the encoded
+    /// bytes. Note that this differs from [Ord] implementation for [Key],
+    /// which is what [BTreeMap] uses.
+    #[cfg(feature = "std")]
+    pub fn cmp_canonical(&self, other: &Key) -> cmp::Ordering {
+        let (a, b) = (self.to_canonical_bytes(), other.to_canonical_bytes());
+        a.len().cmp(&b.len()).then_with(|| a.cmp(&b))
+    }
+
+    #[cfg(feature = "std")]
+    fn to_canonical_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        // encoding a key, which is always a scalar, does not fail.
+        Cbor::from(self).encode(&mut buf).unwrap();
+        buf
+    }
+
+    /// Return whether this key is allowed as a DAG-CBOR map key, that is,
+    /// whether it is encoded as text.
+    pub fn is_dag_cbor(&self) -> bool {
+        matches!(self, Key::Text(_) | Key::Interned(_) | Key::Keyable(_))
+    }
+
     fn to_variant(&self) -> u32 {
         use Key::*;
 
         match self {
             Bool(_) => 20,
             Offset(_) => 30,
-            Text(_) | Keyable(_) => 40,
+            NegOffset(_) => 35,
+            NegInt(_) => 37,
+            Text(_) | Interned(_) | Keyable(_) => 40,
             Bytes(_) => 50,
         }
     }
 }
 
+/// Intern text keys, so that repeated map keys in a document share the
+/// same allocation, refer [Key::Interned].
+#[derive(Default)]
+pub struct KeyInterner {
+    keys: BTreeSet<Arc<str>>,
+}
+
+impl KeyInterner {
+    /// Create an empty interner.
+    pub fn new() -> KeyInterner {
+        KeyInterner::default()
+    }
+
+    /// Return the shared copy of `key`, allocating one on first use.
+    pub fn intern(&mut self, key: &str) -> Arc<str> {
+        match self.keys.get(key) {
+            Some(val) => Arc::clone(val),
+            None => {
+                let val: Arc<str> = Arc::from(key);
+                self.keys.insert(Arc::clone(&val));
+                val
+            }
+        }
+    }
+
+    /// Return the number of unique keys interned so far.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Return whether no keys are interned yet.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
 /// Basic defines IPLD data-model.
+#[derive(Clone)]
 pub enum Basic {
     Null,
+    /// Not part of the IPLD data-model, cbor's undefined is decoded as
+    /// this value only in lenient mode, refer [Basic::from_cbor].
+    Undefined,
     Bool(bool),
     Integer(i128), // TODO: i128 might an overkill, 8 more bytes than 64-bit !!
     Float(f64),
-    Text(Vec<u8>),
-    Bytes(Vec<u8>),
-    Link(Cid),
+    /// Decoded values are immutable, hence boxed slices instead of Vec,
+    /// saving the capacity field and any unused capacity. Text is held as
+    /// raw bytes, that may not be valid UTF-8, refer [Node::as_string].
+    Text(Box<[u8]>),
+    Bytes(Box<[u8]>),
+    /// Boxed, Cid is several times larger than the other variants.
+    Link(Box<Cid>),
     List(Box<dyn Node + 'static>),
     Map(Box<dyn Node + 'static>),
 }
 
 /// Kind of data in data-model.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Kind {
     Null,
+    Undefined,
     Bool,
     Integer,
     Float,
@@ -167,18 +753,139 @@ pub enum Kind {
     Map,
 }
 
+impl Kind {
+    /// Name of this kind as used by IPLD's data model and schemas, like
+    /// `String` for [Kind::Text] and `Int` for [Kind::Integer].
+    /// Undefined is not part of the data model and is named as is.
+    pub fn to_name(&self) -> &'static str {
+        match self {
+            Kind::Null => "Null",
+            Kind::Undefined => "Undefined",
+            Kind::Bool => "Bool",
+            Kind::Integer => "Int",
+            Kind::Float => "Float",
+            Kind::Text => "String",
+            Kind::Bytes => "Bytes",
+            Kind::Link => "Link",
+            Kind::List => "List",
+            Kind::Map => "Map",
+        }
+    }
+
+    /// Rank of this kind, used by [cmp_node] to order values of different
+    /// kinds, in ascending order:
+    ///
+    /// _Null < Undefined < Bool < Integer < Float < Text < Bytes < Link <
+    /// List < Map_
+    ///
+    /// Note that integer and float values are not compared numerically
+    /// with each other, all integers sort before all floats.
+    pub fn to_rank(&self) -> u8 {
+        match self {
+            Kind::Null => 0,
+            Kind::Undefined => 1,
+            Kind::Bool => 2,
+            Kind::Integer => 3,
+            Kind::Float => 4,
+            Kind::Text => 5,
+            Kind::Bytes => 6,
+            Kind::Link => 7,
+            Kind::List => 8,
+            Kind::Map => 9,
+        }
+    }
+}
+
+/// Sort `keys` in DAG-CBOR canonical order, same as the order in which
+/// the encoder writes map entries, refer [Key::cmp_canonical]. Maps built
+/// in this order can be encoded without re-sorting.
+#[cfg(feature = "std")]
+pub fn canonical_key_order(keys: &mut [Key]) {
+    keys.sort_by_cached_key(|key| {
+        let bytes = key.to_canonical_bytes();
+        (bytes.len(), bytes)
+    })
+}
+
+/// Total order on floats, as per IEEE 754 totalOrder predicate. Unlike
+/// `partial_cmp`, `-0.0` sorts before `+0.0` and every value, including
+/// NaN, is equal only to itself. The sign bit of NaN is ignored, so that
+/// all NaNs sort after `+inf`, ordered among themselves by payload.
+pub fn cmp_float(a: f64, b: f64) -> cmp::Ordering {
+    let key = |f: f64| -> i64 {
+        let f = if f.is_nan() { f.abs() } else { f };
+        let bits = f.to_bits() as i64;
+        // flip all non-sign bits for negative numbers, so that larger
+        // magnitude sorts lower.
+        bits ^ ((((bits >> 63) as u64) >> 1) as i64)
+    };
+    key(a).cmp(&key(b))
+}
+
+/// Total order across nodes, nodes are first ordered by the rank of their
+/// kind, refer [Kind::to_rank], and then by value within the same kind.
+/// Bytes and text are compared lexicographically, integers numerically,
+/// floats using [cmp_float], links by their binary form, and lists and maps
+/// recursively entry by entry, map entries in key order, with shorter
+/// sequence sorting first.
+pub fn cmp_node(a: &dyn Node, b: &dyn Node) -> cmp::Ordering {
+    let (x, y) = (a.to_kind(), b.to_kind());
+    match x.to_rank().cmp(&y.to_rank()) {
+        cmp::Ordering::Equal => (),
+        ord => return ord,
+    }
+
+    match x {
+        Kind::Null | Kind::Undefined => cmp::Ordering::Equal,
+        Kind::Bool => a.to_bool().cmp(&b.to_bool()),
+        Kind::Integer => a.to_integer().cmp(&b.to_integer()),
+        Kind::Float => cmp_float(a.to_float().unwrap(), b.to_float().unwrap()),
+        Kind::Text => a.as_text_bytes().cmp(&b.as_text_bytes()),
+        Kind::Bytes => a.as_bytes().cmp(&b.as_bytes()),
+        Kind::Link => a.as_link().cmp(&b.as_link()),
+        Kind::List => {
+            let mut iter = a.iter().zip(b.iter());
+            let ord = iter.find_map(|(a, b)| match cmp_node(a, b) {
+                cmp::Ordering::Equal => None,
+                ord => Some(ord),
+            });
+            ord.unwrap_or_else(|| a.len().cmp(&b.len()))
+        }
+        Kind::Map => {
+            let mut iter = a.iter_entries().zip(b.iter_entries());
+            let ord = iter.find_map(|((k1, v1), (k2, v2))| {
+                match k1.cmp(&k2).then_with(|| cmp_node(v1, v2)) {
+                    cmp::Ordering::Equal => None,
+                    ord => Some(ord),
+                }
+            });
+            ord.unwrap_or_else(|| a.len().cmp(&b.len()))
+        }
+    }
+}
+
+/// Equality implied by content-addressing, two values are equal if they
+/// describe the same data, irrespective of how they are held in memory.
+/// Maps compare order-independently, entry by entry in key order, while
+/// lists compare order-dependently. Floats compare using [cmp_float],
+/// hence `-0.0` and `+0.0` are not equal, while NaN equals itself. Same
+/// as `cmp_node(a, b) == Ordering::Equal`, refer [cmp_node].
+pub fn semantic_eq(a: &Basic, b: &Basic) -> bool {
+    cmp_node(a, b) == cmp::Ordering::Equal
+}
+
 impl Node for Basic {
     fn as_key(&self) -> Option<Key> {
-        use std::str::from_utf8;
+        use core::str::from_utf8;
         use Basic::*;
 
         match self {
-            Null => None,
+            Null | Undefined => None,
             Bool(val) => Some(Key::Bool(val.clone())),
             Integer(val) => Some(Key::Offset(usize::try_from(val.clone()).unwrap())),
             Float(_val) => None,
             Text(val) => Some(Key::Text(from_utf8(val).ok()?.to_string())),
-            Bytes(val) => Some(Key::Bytes(val.clone())),
+            Bytes(val) => Some(Key::Bytes(val.to_vec())),
             Link(_val) => None,
             List(val) => val.as_key(),
             Map(val) => val.as_key(),
@@ -190,6 +897,7 @@ impl Node for Basic {
 
         match self {
             Null => Kind::Null,
+            Undefined => Kind::Undefined,
             Bool(_) => Kind::Bool,
             Integer(_) => Kind::Integer,
             Float(_) => Kind::Float,
@@ -205,7 +913,23 @@ impl Node for Basic {
         match self {
             Basic::List(list) => list.get(key),
             Basic::Map(map) => map.get(key),
-            _ => err_at!(IndexFail, msg: "cannot index scalar type"),
+            _ => err_at!(IndexFail, msg: "cannot index {}", self.kind_name()),
+        }
+    }
+
+    fn get_mut(&mut self, key: &Key) -> Result<&mut dyn Node> {
+        match self {
+            Basic::List(list) => list.get_mut(key),
+            Basic::Map(map) => map.get_mut(key),
+            _ => err_at!(IndexFail, msg: "cannot index {}", self.kind_name()),
+        }
+    }
+
+    fn insert(&mut self, key: Key, value: Box<dyn Node>) -> Result<Option<Box<dyn Node>>> {
+        match self {
+            Basic::List(list) => list.insert(key, value),
+            Basic::Map(map) => map.insert(key, value),
+            _ => err_at!(IndexFail, msg: "cannot index {}", self.kind_name()),
         }
     }
 
@@ -213,7 +937,7 @@ impl Node for Basic {
         match self {
             Basic::List(list) => list.set(key, value),
             Basic::Map(map) => map.set(key, value),
-            _ => err_at!(IndexFail, msg: "cannot index scalar type"),
+            _ => err_at!(IndexFail, msg: "cannot index {}", self.kind_name()),
         }
     }
 
@@ -221,7 +945,7 @@ impl Node for Basic {
         match self {
             Basic::List(list) => list.delete(key),
             Basic::Map(map) => map.delete(key),
-            _ => err_at!(IndexFail, msg: "cannot index scalar type"),
+            _ => err_at!(IndexFail, msg: "cannot index {}", self.kind_name()),
         }
     }
 
@@ -241,6 +965,14 @@ impl Node for Basic {
         }
     }
 
+    fn iter_source_entries<'a>(&'a self) -> Box<dyn Iterator<Item = (Key, &dyn Node)> + 'a> {
+        match self {
+            Basic::List(list) => list.iter_source_entries(),
+            Basic::Map(map) => map.iter_source_entries(),
+            _ => Box::new(vec![].into_iter()),
+        }
+    }
+
     fn len(&self) -> Option<usize> {
         match self {
             Basic::List(list) => list.len(),
@@ -278,18 +1010,35 @@ impl Node for Basic {
     }
 
     fn as_string(&self) -> Option<Result<&str>> {
-        use std::str::from_utf8;
+        use core::str::from_utf8;
 
         match self {
-            Basic::Text(val) => Some(err_at!(FailConvert, from_utf8(val))),
+            Basic::Text(val) => match from_utf8(val) {
+                Ok(text) => Some(Ok(text)),
+                Err(err) => {
+                    let (off, n) = (err.valid_up_to(), val.len());
+                    let res = Err(err);
+                    Some(err_at!(FailConvert, source: res, "(valid up to {} of {} bytes)", off, n))
+                }
+            },
             _ => None,
         }
     }
 
     fn as_ffi_string(&self) -> Option<&str> {
-        use std::str::from_utf8_unchecked;
+        use core::str::from_utf8;
+
+        // text is held as raw bytes and can be constructed with invalid
+        // UTF-8, hence validated instead of assumed.
+        match self {
+            Basic::Text(val) => from_utf8(val).ok(),
+            _ => None,
+        }
+    }
+
+    fn as_text_bytes(&self) -> Option<&[u8]> {
         match self {
-            Basic::Text(val) => Some(unsafe { from_utf8_unchecked(val) }),
+            Basic::Text(val) => Some(val),
             _ => None,
         }
     }
@@ -307,55 +1056,559 @@ impl Node for Basic {
             _ => None,
         }
     }
+
+    fn clone_box(&self) -> Box<dyn Node> {
+        Box::new(self.clone())
+    }
+}
+
+impl Basic {
+    /// Same as [Node::get], but `key` can be any type implementing
+    /// [ToKey], like `node.get_key("field")` or `node.get_key(3)`.
+    pub fn get_key<K: ToKey>(&self, key: K) -> Result<&dyn Node> {
+        self.get(&key.to_key())
+    }
+
+    /// Same as [Node::get], but return `default` instead of failing, if
+    /// `key` is missing or this value is not a container.
+    pub fn get_or<'a>(&'a self, key: &Key, default: &'a dyn Node) -> &'a dyn Node {
+        self.get(key).unwrap_or(default)
+    }
+
+    /// Same as [Basic::get_or], but for `path`, a `/` separated list of
+    /// segments. Return `default` if any of the segments is missing. Links
+    /// along the path are not followed, refer [Basic::resolve_path].
+    pub fn get_path_or<'a>(&'a self, path: &str, default: &'a dyn Node) -> &'a dyn Node {
+        let mut node: &dyn Node = self;
+        for seg in path.split('/').filter(|s| !s.is_empty()) {
+            node = match node.get(&Key::Text(seg.to_string())) {
+                Ok(node) => node,
+                Err(_) => return default,
+            };
+        }
+        node
+    }
+
+    /// Same as `find` on [Node] trait objects, search the tree rooted at
+    /// this value in pre-order, return the first node accepted by `pred`
+    /// along with its path.
+    pub fn find<F>(&self, pred: F) -> Option<(Vec<Key>, &dyn Node)>
+    where
+        F: Fn(&dyn Node) -> bool,
+    {
+        self.as_node().find(pred)
+    }
+
+    /// Set `value` at `path`, where path is a `/` separated list of
+    /// segments, replacing the existing value if any. If `create_missing`
+    /// is true, missing intermediate segments are created as empty maps.
+    /// Fails if any intermediate segment resolves to a scalar value.
+    pub fn set_path(&mut self, path: &str, value: Basic, create_missing: bool) -> Result<()> {
+        let mut segs: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let last = match segs.pop() {
+            Some(last) => last,
+            None => err_at!(BadInput, msg: "empty path {:?}", path)?,
+        };
+
+        let mut node: &mut dyn Node = self;
+        for seg in segs.into_iter() {
+            match node.to_kind() {
+                Kind::List | Kind::Map => (),
+                kind => err_at!(IndexFail, msg: "{} at {:?} in {:?}", kind.to_name(), seg, path)?,
+            }
+            let key = Key::Text(seg.to_string());
+            if create_missing && node.get(&key).is_err() {
+                let map: BTreeMap<Key, Box<dyn Node>> = BTreeMap::new();
+                node.insert(key.clone(), Box::new(Basic::Map(Box::new(map))))?;
+            }
+            node = node.get_mut(&key)?;
+        }
+
+        node.insert(Key::Text(last.to_string()), Box::new(value))?;
+        Ok(())
+    }
+
+    /// Rewrite, in place, every link reachable from this value with the
+    /// CID returned by `f`, say to convert CIDv0 links to CIDv1. Links are
+    /// visited in pre-order, refer [Node::links], rest of the document is
+    /// left as is.
+    pub fn map_links<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Cid) -> Cid,
+    {
+        match self {
+            Basic::Link(cid) => **cid = f(cid),
+            node => map_node_links(node, &mut f),
+        }
+    }
+
+    /// Create a bytes value from hex `text`, either case is accepted.
+    pub fn from_hex(text: &str) -> Result<Basic> {
+        let data = err_at!(
+            FailConvert,
+            Base::Base16Lower.decode(text.to_ascii_lowercase())
+        )?;
+        Ok(Basic::Bytes(data.into_boxed_slice()))
+    }
+
+    /// Create a bytes value from base64 `text`, in standard alphabet,
+    /// with or without the trailing `=` padding.
+    pub fn from_base64(text: &str) -> Result<Basic> {
+        let data = match text.ends_with('=') {
+            true => err_at!(FailConvert, Base::Base64Pad.decode(text))?,
+            false => err_at!(FailConvert, Base::Base64.decode(text))?,
+        };
+        Ok(Basic::Bytes(data.into_boxed_slice()))
+    }
+
+    /// Return the bytes value as lower-case hex text, `None` for any
+    /// other kind.
+    pub fn as_hex(&self) -> Option<String> {
+        self.as_bytes().map(|data| Base::Base16Lower.encode(data))
+    }
+
+    /// Resolve `path`, a `/` separated list of segments, starting from this
+    /// node. When a [Basic::Link] is hit with segments remaining, `fetch`
+    /// is called with the link's CID to load the linked block and
+    /// resolution continues within it. A link at the end of the path is
+    /// returned as is, without fetching. Return an owned copy of the
+    /// resolved value, refer [Node::to_basic]. Fail with `DepthExceeded`
+    /// after following [LINK_HOP_LIMIT] links.
+    pub fn resolve_path<F>(&self, path: &str, mut fetch: F) -> Result<Basic>
+    where
+        F: FnMut(&Cid) -> Result<Basic>,
+    {
+        let segs: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        resolve_node(self, &segs, path, 0, &mut fetch)
+    }
+}
+
+fn resolve_node<F>(
+    node: &dyn Node,
+    segs: &[&str],
+    path: &str,
+    hops: u32,
+    fetch: &mut F,
+) -> Result<Basic>
+where
+    F: FnMut(&Cid) -> Result<Basic>,
+{
+    let (seg, rest) = match segs.split_first() {
+        Some(item) => item,
+        None => return Ok(node.to_basic()),
+    };
+
+    match node.to_kind() {
+        Kind::Link if hops >= LINK_HOP_LIMIT => {
+            err_at!(DepthExceeded, msg: "followed {} links at {:?} in {:?}", hops, seg, path)
+        }
+        Kind::Link => {
+            let block = fetch(node.as_link().unwrap())?;
+            resolve_node(&block, segs, path, hops + 1, fetch)
+        }
+        Kind::List | Kind::Map => {
+            let child = node.get(&Key::Text(seg.to_string()))?;
+            resolve_node(child, rest, path, hops, fetch)
+        }
+        kind => err_at!(IndexFail, msg: "{} at {:?} in {:?}", kind.to_name(), seg, path),
+    }
+}
+
+impl fmt::Display for Basic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        use Basic::*;
+
+        match self {
+            Null => write!(f, "null"),
+            Undefined => write!(f, "undefined"),
+            Bool(val) => write!(f, "{}", val),
+            Integer(val) => write!(f, "{}", val),
+            Float(val) => write!(f, "{}", val),
+            Text(val) => write!(f, "{}", String::from_utf8_lossy(val)),
+            Bytes(val) => write!(f, "<bytes len {}>", val.len()),
+            Link(cid) => match cid.to_text(None) {
+                Ok(text) => write!(f, "{}", text),
+                Err(_) => write!(f, "{}", cid),
+            },
+            List(val) => write!(f, "[list len {}]", val.len().unwrap_or(0)),
+            Map(val) => write!(f, "{{map len {}}}", val.len().unwrap_or(0)),
+        }
+    }
+}
+
+impl fmt::Debug for Basic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        fmt::Debug::fmt(&DebugNode(self), f)
+    }
+}
+
+// structural debug format for any node, text is quoted and containers
+// are formatted item by item, unlike Display that prints a summary.
+struct DebugNode<'a>(&'a dyn Node);
+
+impl<'a> fmt::Debug for DebugNode<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        let node = self.0;
+        match node.to_kind() {
+            Kind::Null => write!(f, "Null"),
+            Kind::Undefined => write!(f, "Undefined"),
+            Kind::Bool => debug_tuple(f, "Bool", &node.to_bool().unwrap()),
+            Kind::Integer => debug_tuple(f, "Integer", &node.to_integer().unwrap()),
+            Kind::Float => debug_tuple(f, "Float", &node.to_float().unwrap()),
+            Kind::Text => {
+                let text = String::from_utf8_lossy(node.as_text_bytes().unwrap());
+                debug_tuple(f, "Text", &text)
+            }
+            Kind::Bytes => debug_tuple(f, "Bytes", &node.as_bytes().unwrap()),
+            Kind::Link => debug_tuple(f, "Link", node.as_link().unwrap()),
+            Kind::List => f.debug_list().entries(node.iter().map(DebugNode)).finish(),
+            Kind::Map => {
+                let iter = node.iter_entries();
+                f.debug_map()
+                    .entries(iter.map(|(key, value)| (key, DebugNode(value))))
+                    .finish()
+            }
+        }
+    }
+}
+
+fn debug_tuple(
+    f: &mut fmt::Formatter,
+    name: &str,
+    val: &dyn fmt::Debug,
+) -> result::Result<(), fmt::Error> {
+    f.debug_tuple(name).field(val).finish()
+}
+
+// Scalar comparisons, mismatching kinds compare as not-equal.
+
+impl PartialEq<i128> for Basic {
+    fn eq(&self, other: &i128) -> bool {
+        matches!(self, Basic::Integer(val) if val == other)
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Basic {
+    fn eq(&self, other: &&'a str) -> bool {
+        matches!(self, Basic::Text(val) if val.as_ref() == other.as_bytes())
+    }
+}
+
+impl PartialEq<bool> for Basic {
+    fn eq(&self, other: &bool) -> bool {
+        matches!(self, Basic::Bool(val) if val == other)
+    }
+}
+
+impl PartialEq<f64> for Basic {
+    fn eq(&self, other: &f64) -> bool {
+        match self {
+            Basic::Float(val) => val.partial_cmp(other) == Some(cmp::Ordering::Equal),
+            _ => false,
+        }
+    }
+}
+
+impl From<bool> for Basic {
+    fn from(val: bool) -> Basic {
+        Basic::Bool(val)
+    }
+}
+
+impl From<i128> for Basic {
+    fn from(val: i128) -> Basic {
+        Basic::Integer(val)
+    }
 }
 
+impl From<f64> for Basic {
+    fn from(val: f64) -> Basic {
+        Basic::Float(val)
+    }
+}
+
+impl<'a> From<&'a str> for Basic {
+    fn from(val: &'a str) -> Basic {
+        Basic::Text(val.as_bytes().into())
+    }
+}
+
+impl From<String> for Basic {
+    fn from(val: String) -> Basic {
+        Basic::Text(val.into_bytes().into_boxed_slice())
+    }
+}
+
+impl From<Cid> for Basic {
+    fn from(val: Cid) -> Basic {
+        Basic::Link(Box::new(val))
+    }
+}
+
+impl FromIterator<Basic> for Basic {
+    fn from_iter<I: IntoIterator<Item = Basic>>(iter: I) -> Basic {
+        let list: Vec<Box<dyn Node>> = iter
+            .into_iter()
+            .map(|item| Box::new(item) as Box<dyn Node>)
+            .collect();
+        Basic::List(Box::new(list))
+    }
+}
+
+/// Iterate over values of a list or map, same as [Node::iter], scalar
+/// kinds yield nothing.
+impl<'a> IntoIterator for &'a Basic {
+    type Item = &'a dyn Node;
+    type IntoIter = Box<dyn Iterator<Item = &'a dyn Node> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Node::iter(self)
+    }
+}
+
+impl FromIterator<(Key, Basic)> for Basic {
+    fn from_iter<I: IntoIterator<Item = (Key, Basic)>>(iter: I) -> Basic {
+        let map: BTreeMap<Key, Box<dyn Node>> = iter
+            .into_iter()
+            .map(|(key, value)| (key, Box::new(value) as Box<dyn Node>))
+            .collect();
+        Basic::Map(Box::new(map))
+    }
+}
+
+impl<'a> TryFrom<&'a dyn Node> for i64 {
+    type Error = Error;
+
+    fn try_from(node: &'a dyn Node) -> Result<i64> {
+        match node.to_integer() {
+            Some(num) => err_at!(FailConvert, i64::try_from(num), "integer {}", num),
+            None => err_at!(FailConvert, msg: "{} is not an integer", node.kind_name()),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a dyn Node> for u64 {
+    type Error = Error;
+
+    fn try_from(node: &'a dyn Node) -> Result<u64> {
+        match node.to_integer() {
+            Some(num) => err_at!(FailConvert, u64::try_from(num), "integer {}", num),
+            None => err_at!(FailConvert, msg: "{} is not an integer", node.kind_name()),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a dyn Node> for f64 {
+    type Error = Error;
+
+    fn try_from(node: &'a dyn Node) -> Result<f64> {
+        match node.to_float() {
+            Some(val) => Ok(val),
+            None => err_at!(FailConvert, msg: "{} is not a float", node.kind_name()),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a dyn Node> for bool {
+    type Error = Error;
+
+    fn try_from(node: &'a dyn Node) -> Result<bool> {
+        match node.to_bool() {
+            Some(val) => Ok(val),
+            None => err_at!(FailConvert, msg: "{} is not a bool", node.kind_name()),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a dyn Node> for String {
+    type Error = Error;
+
+    fn try_from(node: &'a dyn Node) -> Result<String> {
+        match node.as_string() {
+            Some(val) => Ok(val?.to_string()),
+            None => err_at!(FailConvert, msg: "{} is not a text", node.kind_name()),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a dyn Node> for Vec<u8> {
+    type Error = Error;
+
+    fn try_from(node: &'a dyn Node) -> Result<Vec<u8>> {
+        match node.as_bytes() {
+            Some(val) => Ok(val.to_vec()),
+            None => err_at!(FailConvert, msg: "{} is not a bytes", node.kind_name()),
+        }
+    }
+}
+
+// same as converting from `&dyn Node`, for `&Basic`.
+macro_rules! try_from_basic {
+    ($($t:ty),*) => (
+        $(
+            impl<'a> TryFrom<&'a Basic> for $t {
+                type Error = Error;
+
+                fn try_from(val: &'a Basic) -> Result<$t> {
+                    <$t>::try_from(val as &dyn Node)
+                }
+            }
+        )*
+    );
+}
+
+try_from_basic![i64, u64, f64, bool, String, Vec<u8>];
+
 impl TryFrom<Cbor> for Basic {
     type Error = Error;
 
     fn try_from(val: Cbor) -> Result<Basic> {
+        Basic::from_cbor(val, true)
+    }
+}
+
+impl Basic {
+    /// Convert Cbor value into data-model. In `strict` mode, as required
+    /// by DAG-CBOR, map keys must be text and unique, otherwise integer
+    /// and bytes keys are accepted as [Key::Offset], [Key::NegInt] and
+    /// [Key::Bytes], and for duplicate keys the last value wins. Similarly
+    /// text must be valid UTF-8 in `strict` mode, otherwise invalid text is
+    /// converted to [Basic::Bytes]. And undefined is rejected in `strict`
+    /// mode, otherwise converted to [Basic::Undefined]. DAG-CBOR allows
+    /// only 64-bit floats, 16-bit and 32-bit floats are rejected in
+    /// `strict` mode. Likewise bignums, tag 2 and 3, are rejected in
+    /// `strict` mode, otherwise converted to [Basic::Integer], failing
+    /// if they are beyond the range of i128.
+    pub fn from_cbor(val: Cbor, strict: bool) -> Result<Basic> {
+        Basic::do_from_cbor(val, strict, false, false)
+    }
+
+    /// Same as [Basic::from_cbor], except that bignums beyond the range of
+    /// i128 are clamped to `i128::MAX` or `i128::MIN` instead of failing,
+    /// refer [crate::ipld::cbor::Tag::to_integer].
+    pub fn from_cbor_saturating(val: Cbor, strict: bool) -> Result<Basic> {
+        Basic::do_from_cbor(val, strict, false, true)
+    }
+
+    /// Same as [Basic::from_cbor], except that maps are backed by
+    /// `Vec<(Key, Box<dyn Node>)>` storing the entries in source order,
+    /// refer [Node::iter_source_entries]. Iteration is still in key order,
+    /// refer [Node::iter], and canonical encoder shall still sort the
+    /// entries.
+    pub fn from_cbor_ordered(val: Cbor, strict: bool) -> Result<Basic> {
+        Basic::do_from_cbor(val, strict, true, false)
+    }
+
+    /// Convert the subset of cbor values that can never fail conversion,
+    /// in both strict and lenient mode, refer [Basic::from_cbor]. That is
+    /// integers, valid UTF-8 text, bytes, bool, null, 64-bit floats and
+    /// links. Any other value, including lists and maps, is handed back
+    /// as `Err(val)` for the fallible path.
+    pub fn from_cbor_infallible(mut val: Cbor) -> result::Result<Basic, Cbor> {
         use crate::ipld::cbor::{self, Cbor::*};
         use Basic::*;
 
-        let kind = match val {
-            Major0(_, num) => Integer(num.into()),
-            Major1(_, num) => Integer(-(i128::from(num) + 1)),
-            Major2(_, byts) => Bytes(byts),
-            Major3(_, text) => Text(text),
+        // Cbor implements Drop, hence its fields are taken, not moved out.
+        let kind = match &mut val {
+            Major0(_, num) => Integer((*num).into()),
+            Major1(_, num) => Integer(-(i128::from(*num) + 1)),
+            Major2(_, byts) => Bytes(mem::take(byts).into_boxed_slice()),
+            Major3(_, text) if str::from_utf8(text).is_ok() => {
+                Text(mem::take(text).into_boxed_slice())
+            }
+            Major6(_, cbor::Tag::Link(cid)) => Link(Box::new(cid.clone())),
+            Major7(_, cbor::SimpleValue::True) => Bool(true),
+            Major7(_, cbor::SimpleValue::False) => Bool(false),
+            Major7(_, cbor::SimpleValue::Null) => Null,
+            Major7(_, cbor::SimpleValue::F64(val)) => Float(*val),
+            _ => return Err(val),
+        };
+
+        Ok(kind)
+    }
+
+    fn do_from_cbor(val: Cbor, strict: bool, ordered: bool, saturate: bool) -> Result<Basic> {
+        use crate::ipld::cbor::{self, Cbor::*};
+        use Basic::*;
+
+        let mut val = match Basic::from_cbor_infallible(val) {
+            Ok(kind) => return Ok(kind),
+            Err(val) => val,
+        };
+
+        let kind = match &mut val {
+            Major3(_, text) if strict => {
+                err_at!(FailConvert, source: str::from_utf8(text))?;
+                Text(mem::take(text).into_boxed_slice())
+            }
+            Major3(_, text) => match str::from_utf8(text) {
+                Ok(_) => Text(mem::take(text).into_boxed_slice()),
+                Err(_) => Bytes(mem::take(text).into_boxed_slice()),
+            },
             Major4(_, list) => {
                 let mut klist: Vec<Box<dyn Node>> = vec![];
-                for item in list.into_iter() {
-                    klist.push(Box::new(Basic::try_from(item)?));
+                for item in list.drain(..) {
+                    klist.push(Box::new(Basic::do_from_cbor(
+                        item, strict, ordered, saturate,
+                    )?));
                 }
                 List(Box::new(klist))
             }
             Major5(_, dict) => {
-                let mut kdict: BTreeMap<Key, Box<dyn Node>> = BTreeMap::new();
-                for (k, v) in dict.into_iter() {
-                    kdict.insert(Key::Text(k), Box::new(Basic::try_from(v)?));
+                let mut entries: Vec<(Key, Box<dyn Node>)> = vec![];
+                // index of keys into entries, to detect duplicate keys.
+                let mut index: BTreeMap<Key, usize> = BTreeMap::new();
+                for (k, v) in dict.drain(..) {
+                    if strict && !matches!(k, Key::Text(_) | Key::Interned(_)) {
+                        err_at!(FailConvert, msg: "non-text map key {}", k)?
+                    }
+                    let v: Box<dyn Node> =
+                        Box::new(Basic::do_from_cbor(v, strict, ordered, saturate)?);
+                    match index.get(&k) {
+                        Some(_) if strict => err_at!(FailConvert, msg: "duplicate map key {}", k)?,
+                        Some(off) => entries[*off].1 = v,
+                        None => {
+                            index.insert(k.clone(), entries.len());
+                            entries.push((k, v));
+                        }
+                    }
+                }
+                match ordered {
+                    true => Map(Box::new(entries)),
+                    false => {
+                        let kdict: BTreeMap<Key, Box<dyn Node>> = entries.into_iter().collect();
+                        Map(Box::new(kdict))
+                    }
                 }
-                Map(Box::new(kdict))
             }
-            Major6(_, cbor::Tag::Link(cid)) => Link(cid),
+            Major6(_, _) if strict => err_at!(FailConvert, msg: "bignum tag in strict mode")?,
+            Major6(_, tag) => match tag.to_integer(saturate) {
+                Some(num) => Integer(num?),
+                None => err_at!(Fatal, msg: "unreachable")?,
+            },
             Major7(_, cbor::SimpleValue::Unassigned) => {
                 err_at!(FailConvert, msg: "unassigned simple-value")?
             }
-            Major7(_, cbor::SimpleValue::True) => Bool(true),
-            Major7(_, cbor::SimpleValue::False) => Bool(false),
-            Major7(_, cbor::SimpleValue::Null) => Null,
-            Major7(_, cbor::SimpleValue::Undefined) => {
+            Major7(_, cbor::SimpleValue::Undefined) if strict => {
                 err_at!(FailConvert, msg: "undefined simple-value")?
             }
+            Major7(_, cbor::SimpleValue::Undefined) => Undefined,
             Major7(_, cbor::SimpleValue::Reserved24(_)) => {
                 err_at!(FailConvert, msg: "single byte simple-value")?
             }
-            Major7(_, cbor::SimpleValue::F16(_)) => {
-                err_at!(FailConvert, msg: "half-precision not supported")?
+            Major7(_, cbor::SimpleValue::F16(_)) if strict => {
+                err_at!(FailConvert, msg: "16-bit float, expected 64-bit")?
             }
-            Major7(_, cbor::SimpleValue::F32(val)) => Float(val as f64),
-            Major7(_, cbor::SimpleValue::F64(val)) => Float(val),
+            Major7(_, cbor::SimpleValue::F32(_)) if strict => {
+                err_at!(FailConvert, msg: "32-bit float, expected 64-bit")?
+            }
+            Major7(_, cbor::SimpleValue::F16(val)) => Float(cbor::SimpleValue::f16_to_f64(*val)),
+            Major7(_, cbor::SimpleValue::F32(val)) => Float(*val as f64),
             Major7(_, cbor::SimpleValue::Break) => {
                 err_at!(FailConvert, msg: "indefinite length not supported")?
             }
+            // handled by from_cbor_infallible.
+            _ => err_at!(Fatal, msg: "unreachable")?,
         };
 
         Ok(kind)
@@ -378,6 +1631,17 @@ impl Node for BTreeMap<Key, Box<dyn Node>> {
         }
     }
 
+    fn get_mut(&mut self, key: &Key) -> Result<&mut dyn Node> {
+        match self.get_mut(key) {
+            Some(val) => Ok(val.as_mut()),
+            None => err_at!(IndexFail, msg: "missing key in btreemap {}", key),
+        }
+    }
+
+    fn insert(&mut self, key: Key, value: Box<dyn Node>) -> Result<Option<Box<dyn Node>>> {
+        Ok(self.insert(key, value))
+    }
+
     fn set(&self, _key: &Key, _value: Box<dyn Node>) -> Result<Box<dyn Node>> {
         todo!()
     }
@@ -429,6 +1693,118 @@ impl Node for BTreeMap<Key, Box<dyn Node>> {
     fn as_link(&self) -> Option<&Cid> {
         None
     }
+
+    fn clone_box(&self) -> Box<dyn Node> {
+        Box::new(self.clone())
+    }
+}
+
+/// Map backing that preserves the insertion order of its entries, lookups
+/// are linear in the number of entries. Like other map backings, iteration
+/// is in key order, entries are sorted on every call to iter. Use
+/// [Node::iter_source_entries] for insertion order.
+impl Node for Vec<(Key, Box<dyn Node>)> {
+    fn as_key(&self) -> Option<Key> {
+        None
+    }
+
+    fn to_kind(&self) -> Kind {
+        Kind::Map
+    }
+
+    fn get(&self, key: &Key) -> Result<&dyn Node> {
+        match self.as_slice().iter().find(|(k, _)| k == key) {
+            Some((_, val)) => Ok(val.as_ref()),
+            None => err_at!(IndexFail, msg: "missing key in ordered map {}", key),
+        }
+    }
+
+    fn get_mut(&mut self, key: &Key) -> Result<&mut dyn Node> {
+        match self.as_mut_slice().iter_mut().find(|(k, _)| k == key) {
+            Some((_, val)) => Ok(val.as_mut()),
+            None => err_at!(IndexFail, msg: "missing key in ordered map {}", key),
+        }
+    }
+
+    fn insert(&mut self, key: Key, value: Box<dyn Node>) -> Result<Option<Box<dyn Node>>> {
+        match self.as_mut_slice().iter_mut().find(|(k, _)| k == &key) {
+            Some((_, val)) => Ok(Some(core::mem::replace(val, value))),
+            None => {
+                self.push((key, value));
+                Ok(None)
+            }
+        }
+    }
+
+    fn set(&self, key: &Key, value: Box<dyn Node>) -> Result<Box<dyn Node>> {
+        let mut map = self.clone();
+        Node::insert(&mut map, key.clone(), value)?;
+        Ok(Box::new(map))
+    }
+
+    fn delete(&self, key: &Key) -> Result<Box<dyn Node>> {
+        let mut map = self.clone();
+        match map.as_slice().iter().position(|(k, _)| k == key) {
+            Some(off) => {
+                map.remove(off);
+                Ok(Box::new(map))
+            }
+            None => err_at!(IndexFail, msg: "missing key in ordered map {}", key),
+        }
+    }
+
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &dyn Node> + 'a> {
+        Box::new(sorted_entries(self).into_iter().map(|(_, v)| v.as_ref()))
+    }
+
+    fn iter_entries<'a>(&'a self) -> Box<dyn Iterator<Item = (Key, &dyn Node)> + 'a> {
+        let iter = sorted_entries(self).into_iter();
+        Box::new(iter.map(|(k, v)| (k.clone(), v.as_ref())))
+    }
+
+    fn iter_source_entries<'a>(&'a self) -> Box<dyn Iterator<Item = (Key, &dyn Node)> + 'a> {
+        Box::new(self.as_slice().iter().map(|(k, v)| (k.clone(), v.as_ref())))
+    }
+
+    fn len(&self) -> Option<usize> {
+        Some(self.as_slice().len())
+    }
+
+    fn is_null(&self) -> bool {
+        false
+    }
+
+    fn to_bool(&self) -> Option<bool> {
+        None
+    }
+
+    fn to_integer(&self) -> Option<i128> {
+        None
+    }
+
+    fn to_float(&self) -> Option<f64> {
+        None
+    }
+
+    fn as_string(&self) -> Option<Result<&str>> {
+        None
+    }
+
+    fn as_ffi_string(&self) -> Option<&str> {
+        None
+    }
+
+    fn as_bytes(&self) -> Option<&[u8]> {
+        None
+    }
+
+    fn as_link(&self) -> Option<&Cid> {
+        None
+    }
+
+    fn clone_box(&self) -> Box<dyn Node> {
+        Box::new(self.clone())
+    }
 }
 
 impl Node for Vec<Box<dyn Node>> {
@@ -446,14 +1822,81 @@ impl Node for Vec<Box<dyn Node>> {
                 Some(val) => Ok(val.as_ref()),
                 None => err_at!(IndexFail, msg: "missing off in vec {}", off),
             },
+            Key::NegOffset(off) => {
+                let n = isize::try_from(self.as_slice().len()).unwrap_or(isize::MAX);
+                match if *off < 0 { n + off } else { *off } {
+                    o if o >= 0 => self.get(&Key::Offset(o as usize)),
+                    _ => err_at!(IndexFail, msg: "missing neg-off in vec {}", off),
+                }
+            }
             Key::Text(key) => {
-                let off: usize = err_at!(FailConvert, key.parse())?;
+                let off = parse_offset(key)?;
+                self.get(&Key::Offset(off))
+            }
+            Key::Interned(key) => {
+                let off = parse_offset(key)?;
                 self.get(&Key::Offset(off))
             }
             _ => err_at!(IndexFail, msg: "can't index scalar-kind"),
         }
     }
 
+    fn get_mut(&mut self, key: &Key) -> Result<&mut dyn Node> {
+        match key {
+            Key::Offset(off) => match self.as_mut_slice().get_mut(*off) {
+                Some(val) => Ok(val.as_mut()),
+                None => err_at!(IndexFail, msg: "missing off in vec {}", off),
+            },
+            Key::NegOffset(off) => {
+                let n = isize::try_from(self.as_slice().len()).unwrap_or(isize::MAX);
+                match if *off < 0 { n + off } else { *off } {
+                    o if o >= 0 => self.get_mut(&Key::Offset(o as usize)),
+                    _ => err_at!(IndexFail, msg: "missing neg-off in vec {}", off),
+                }
+            }
+            Key::Text(key) => {
+                let off = parse_offset(key)?;
+                self.get_mut(&Key::Offset(off))
+            }
+            Key::Interned(key) => {
+                let off = parse_offset(key)?;
+                self.get_mut(&Key::Offset(off))
+            }
+            _ => err_at!(IndexFail, msg: "can't index scalar-kind"),
+        }
+    }
+
+    fn insert(&mut self, key: Key, value: Box<dyn Node>) -> Result<Option<Box<dyn Node>>> {
+        let n = self.as_slice().len();
+        match key {
+            Key::Offset(off) if off < n => Ok(Some(core::mem::replace(
+                &mut self.as_mut_slice()[off],
+                value,
+            ))),
+            Key::Offset(off) if off == n => {
+                self.push(value);
+                Ok(None)
+            }
+            Key::Offset(off) => err_at!(IndexFail, msg: "off {} beyond vec {}", off, n),
+            Key::NegOffset(off) => {
+                let n = isize::try_from(n).unwrap_or(isize::MAX);
+                match if off < 0 { n + off } else { off } {
+                    o if o >= 0 => Node::insert(self, Key::Offset(o as usize), value),
+                    _ => err_at!(IndexFail, msg: "missing neg-off in vec {}", off),
+                }
+            }
+            Key::Text(key) => {
+                let off = parse_offset(key)?;
+                Node::insert(self, Key::Offset(off), value)
+            }
+            Key::Interned(key) => {
+                let off = parse_offset(key)?;
+                Node::insert(self, Key::Offset(off), value)
+            }
+            _ => err_at!(IndexFail, msg: "can't index scalar-kind"),
+        }
+    }
+
     fn set(&self, _key: &Key, _value: Box<dyn Node>) -> Result<Box<dyn Node>> {
         todo!()
     }
@@ -509,6 +1952,10 @@ impl Node for Vec<Box<dyn Node>> {
     fn as_link(&self) -> Option<&Cid> {
         None
     }
+
+    fn clone_box(&self) -> Box<dyn Node> {
+        Box::new(self.clone())
+    }
 }
 
 // NOTE: Operational behaviour on data.
@@ -518,3 +1965,7 @@ impl Node for Vec<Box<dyn Node>> {
 // * Schema-matching on deserialized kind.
 // * Indexing operation within list and map kinds.
 // * Iteration on list and map kinds.
+
+#[cfg(test)]
+#[path = "kind_test.rs"]
+mod kind_test;
@@ -0,0 +1,1520 @@
+use multibase::Base;
+
+use std::error::Error as StdError;
+
+use super::*;
+use crate::{multicodec, ErrorKind};
+
+fn make_cid(data: &[u8]) -> Cid {
+    Cid::new_v1(Base::Base32Lower, multicodec::DAG_CBOR.into(), data).unwrap()
+}
+
+fn make_list(items: Vec<Basic>) -> Basic {
+    let list: Vec<Box<dyn Node>> = items
+        .into_iter()
+        .map(|item| Box::new(item) as Box<dyn Node>)
+        .collect();
+    Basic::List(Box::new(list))
+}
+
+fn make_map(entries: Vec<(&str, Basic)>) -> Basic {
+    let mut map: BTreeMap<Key, Box<dyn Node>> = BTreeMap::new();
+    for (key, value) in entries.into_iter() {
+        map.insert(Key::Text(key.to_string()), Box::new(value));
+    }
+    Basic::Map(Box::new(map))
+}
+
+#[test]
+fn test_walk() {
+    let doc = make_map(vec![
+        ("a", Basic::Integer(1)),
+        (
+            "b",
+            make_list(vec![Basic::Bool(true), Basic::Text(b"x".to_vec().into())]),
+        ),
+    ]);
+
+    let mut visited: Vec<(Vec<Key>, Kind)> = vec![];
+    doc.walk(&mut |path, node| visited.push((path.to_vec(), node.to_kind())));
+
+    let a = Key::Text("a".to_string());
+    let b = Key::Text("b".to_string());
+    let refs = vec![
+        (vec![], Kind::Map),
+        (vec![a], Kind::Integer),
+        (vec![b.clone()], Kind::List),
+        (vec![b.clone(), Key::Offset(0)], Kind::Bool),
+        (vec![b, Key::Offset(1)], Kind::Text),
+    ];
+    assert_eq!(visited, refs);
+}
+
+#[test]
+fn test_links() {
+    let (cid1, cid2, cid3) = (make_cid(b"one"), make_cid(b"two"), make_cid(b"three"));
+    let doc = make_map(vec![
+        (
+            "a",
+            make_list(vec![
+                Basic::Link(cid1.clone().into()),
+                Basic::Integer(10),
+                Basic::Link(cid2.clone().into()),
+            ]),
+        ),
+        ("b", make_map(vec![("c", Basic::Link(cid3.clone().into()))])),
+        ("d", Basic::Integer(1)),
+    ]);
+
+    let a = Key::Text("a".to_string());
+    let refs = vec![
+        (vec![a.clone(), Key::Offset(0)], cid1),
+        (vec![a, Key::Offset(2)], cid2),
+        (
+            vec![Key::Text("b".to_string()), Key::Text("c".to_string())],
+            cid3,
+        ),
+    ];
+    assert_eq!(doc.links(), refs);
+
+    let mut cids = vec![];
+    doc.for_each_link(&mut |cid| cids.push(cid.clone()));
+    let refs: Vec<Cid> = refs.into_iter().map(|(_, cid)| cid).collect();
+    assert_eq!(cids, refs);
+
+    let doc = make_map(vec![("x", make_list(vec![Basic::Null]))]);
+    assert!(doc.links().is_empty());
+}
+
+#[test]
+fn test_normalize() {
+    let encode = |doc: &Basic| -> Vec<u8> {
+        let mut buf = vec![];
+        doc.encode_with(&EncodeOptions::default(), &mut buf)
+            .unwrap();
+        buf
+    };
+
+    // {"b": [1.0, 2], "a": {"y": null, "x": true}}, in source order.
+    let text = "a2616282f93c00026161a26179f66178f5";
+    let val = Cbor::from_base(text, Base::Base16Lower).unwrap();
+    let a = Basic::from_cbor_ordered(val, false).unwrap();
+    let b = make_map(vec![
+        (
+            "a",
+            make_map(vec![("x", Basic::Bool(true)), ("y", Basic::Null)]),
+        ),
+        ("b", make_list(vec![Basic::Float(1.0), Basic::Integer(2)])),
+    ]);
+
+    let (a, b) = (a.normalize().unwrap(), b.normalize().unwrap());
+    assert_eq!(encode(&a), encode(&b));
+    let list = a.get_key("b").unwrap();
+    assert_eq!(list.get_key(0).unwrap().to_kind(), Kind::Float);
+
+    let doc = make_list(vec![Basic::Float(std::f64::NAN)]);
+    let err = doc.normalize().unwrap_err();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+    assert!(make_list(vec![Basic::Undefined]).normalize().is_err());
+
+    // duplicate keys in a Vec backed map would collapse while rebuilding.
+    let entries: Vec<(Key, Box<dyn Node>)> = vec![
+        (Key::Text("a".to_string()), Box::new(Basic::Integer(1))),
+        (Key::Text("a".to_string()), Box::new(Basic::Integer(2))),
+    ];
+    let doc = make_list(vec![Basic::Map(Box::new(entries))]);
+    let err = doc.normalize().unwrap_err();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+    assert!(err.to_string().contains("duplicate map key"), "{}", err);
+}
+
+#[test]
+fn test_map_links() {
+    let (cid1, cid2) = (Cid::new_v0(b"one").unwrap(), Cid::new_v0(b"two").unwrap());
+    let mut doc = make_map(vec![
+        (
+            "a",
+            make_list(vec![Basic::Link(cid1.clone().into()), Basic::Integer(10)]),
+        ),
+        ("b", make_map(vec![("c", Basic::Link(cid2.clone().into()))])),
+        ("d", Basic::from("text")),
+    ]);
+    let orig = doc.clone();
+
+    doc.map_links(|cid| cid.clone().into_v1());
+
+    let links: Vec<Cid> = doc.links().into_iter().map(|(_, cid)| cid).collect();
+    assert_eq!(links, vec![cid1.clone().into_v1(), cid2.into_v1()]);
+    assert!(links
+        .iter()
+        .all(|cid| cid.to_version() == crate::cid::Version::One));
+
+    // rest of the document is unchanged.
+    let paths = |doc: &Basic| -> Vec<(Vec<Key>, Kind)> {
+        let mut acc = vec![];
+        doc.walk(&mut |path, node| acc.push((path.to_vec(), node.to_kind())));
+        acc
+    };
+    assert_eq!(paths(&doc), paths(&orig));
+    assert_eq!(
+        doc.get_key("a").unwrap().get_key(1).unwrap().to_integer(),
+        Some(10)
+    );
+    assert_eq!(doc.get_key("d").unwrap().as_ffi_string(), Some("text"));
+
+    let mut link = Basic::Link(cid1.clone().into());
+    link.map_links(|cid| cid.clone().into_v1());
+    assert_eq!(link.as_link(), Some(&cid1.into_v1()));
+}
+
+#[test]
+fn test_clone() {
+    let doc = make_map(vec![
+        ("a", Basic::Integer(1)),
+        ("b", make_list(vec![Basic::Text(b"x".to_vec().into())])),
+    ]);
+    let copy = doc.clone();
+
+    let a = Key::Text("a".to_string());
+    let b = Key::Text("b".to_string());
+
+    let (x, y) = (doc.get(&a).unwrap(), copy.get(&a).unwrap());
+    assert_eq!(x.to_integer(), y.to_integer());
+    assert!(!std::ptr::eq(
+        x as *const dyn Node as *const u8,
+        y as *const dyn Node as *const u8
+    ));
+
+    let y = copy.get(&b).unwrap().get(&Key::Offset(0)).unwrap();
+    assert_eq!(y.as_ffi_string(), Some("x"));
+
+    std::mem::drop(doc);
+    assert_eq!(copy.len(), Some(2));
+    assert_eq!(copy.get(&a).unwrap().to_integer(), Some(1));
+}
+
+#[test]
+fn test_dag_cbor_float() {
+    let val = Basic::Float(f64::NAN);
+    assert!(val.is_dag_cbor_valid().is_err());
+    assert!(val.to_dag_cbor_bytes().is_err());
+    assert_eq!(val.to_cbor_bytes().unwrap().len(), 9);
+
+    let doc = make_map(vec![("a", make_list(vec![Basic::Float(f64::INFINITY)]))]);
+    assert!(doc.to_dag_cbor_bytes().is_err());
+    assert!(doc.to_cbor_bytes().is_ok());
+
+    let val = Basic::Float(1.5);
+    let refv = vec![0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0];
+    assert_eq!(val.to_dag_cbor_bytes().unwrap(), refv);
+}
+
+#[test]
+fn test_key_cmp_canonical() {
+    let (z, aa) = (Key::Text("z".to_string()), Key::Text("aa".to_string()));
+    assert_eq!(z.cmp_canonical(&aa), cmp::Ordering::Less);
+    assert_eq!(aa.cmp_canonical(&z), cmp::Ordering::Greater);
+    assert_eq!(z.cmp(&aa), cmp::Ordering::Greater);
+
+    let (ab, aa) = (Key::Text("ab".to_string()), aa);
+    assert_eq!(aa.cmp_canonical(&ab), cmp::Ordering::Less);
+    assert_eq!(aa.cmp_canonical(&aa.clone()), cmp::Ordering::Equal);
+
+    let doc = make_map(vec![("aa", Basic::Integer(1)), ("z", Basic::Integer(2))]);
+    let refv = vec![0xa2, 0x61, 0x7a, 0x02, 0x62, 0x61, 0x61, 0x01];
+    assert_eq!(doc.to_dag_cbor_bytes().unwrap(), refv);
+}
+
+#[test]
+fn test_error_source() {
+    let val = Basic::Text(vec![0x61, 0xff, 0xfe].into());
+    let err = val.as_string().unwrap().unwrap_err();
+    assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+
+    let src = err.source().unwrap();
+    assert!(src.downcast_ref::<std::str::Utf8Error>().is_some());
+}
+
+#[test]
+fn test_error_kind() {
+    let doc = make_list(vec![Basic::Integer(1), Basic::Integer(2)]);
+    let err = doc.get(&Key::Offset(2)).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::IndexFail);
+
+    let err = doc.get(&Key::Text("abc".to_string())).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::FailConvert);
+}
+
+#[test]
+fn test_neg_offset() {
+    let doc = make_list(vec![
+        Basic::Integer(1),
+        Basic::Integer(2),
+        Basic::Integer(3),
+    ]);
+
+    let val = doc.get(&Key::NegOffset(-1)).unwrap();
+    assert_eq!(val.to_integer(), Some(3));
+    let val = doc.get(&Key::NegOffset(-3)).unwrap();
+    assert_eq!(val.to_integer(), Some(1));
+    let err = doc.get(&Key::NegOffset(-4)).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::IndexFail);
+
+    let val = doc.get(&Key::Offset(1)).unwrap();
+    assert_eq!(val.to_integer(), Some(2));
+    let val = doc.get(&Key::NegOffset(1)).unwrap();
+    assert_eq!(val.to_integer(), Some(2));
+}
+
+#[test]
+fn test_get_mut() {
+    let mut doc = make_map(vec![(
+        "a",
+        make_list(vec![Basic::Integer(1), make_list(vec![Basic::Integer(2)])]),
+    )]);
+
+    let a = Key::Text("a".to_string());
+    let inner = doc.get_mut(&a).unwrap().get_mut(&Key::Offset(1)).unwrap();
+    let old = inner
+        .insert(Key::Offset(0), Box::new(Basic::Integer(20)))
+        .unwrap();
+    assert_eq!(old.unwrap().to_integer(), Some(2));
+    assert!(inner
+        .insert(Key::Offset(1), Box::new(Basic::Integer(30)))
+        .unwrap()
+        .is_none());
+    let err = inner
+        .insert(Key::Offset(3), Box::new(Basic::Null))
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::IndexFail);
+
+    let inner = doc.get(&a).unwrap().get(&Key::Offset(1)).unwrap();
+    assert_eq!(inner.len(), Some(2));
+    assert_eq!(inner.get(&Key::Offset(0)).unwrap().to_integer(), Some(20));
+    assert_eq!(
+        inner.get(&Key::NegOffset(-1)).unwrap().to_integer(),
+        Some(30)
+    );
+
+    let mut scalar = Basic::Integer(1);
+    let err = scalar.get_mut(&Key::Offset(0)).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::IndexFail);
+}
+
+#[test]
+fn test_set_path() {
+    let mut doc = make_map(vec![]);
+    doc.set_path("a/b/c", Basic::Integer(10), true).unwrap();
+    let val = doc
+        .get(&Key::Text("a".to_string()))
+        .unwrap()
+        .get(&Key::Text("b".to_string()))
+        .unwrap()
+        .get(&Key::Text("c".to_string()))
+        .unwrap();
+    assert_eq!(val.to_integer(), Some(10));
+
+    doc.set_path("a/b/c", Basic::Integer(20), false).unwrap();
+    let val = doc.get(&Key::Text("a".to_string())).unwrap();
+    let val = val.get(&Key::Text("b".to_string())).unwrap();
+    assert_eq!(
+        val.get(&Key::Text("c".to_string())).unwrap().to_integer(),
+        Some(20)
+    );
+
+    let mut doc = make_map(vec![]);
+    let err = doc.set_path("a/b", Basic::Null, false).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::IndexFail);
+
+    let mut doc = make_map(vec![("a", Basic::Integer(1))]);
+    let err = doc.set_path("a/b/c", Basic::Null, true).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::IndexFail);
+}
+
+#[test]
+fn test_from_cbor_map_keys() {
+    // {"a": 1}
+    let text = "a1616101";
+    let val = Cbor::from_base(text, Base::Base16Lower).unwrap();
+    let doc = Basic::try_from(val).unwrap();
+    let val = doc.get(&Key::Text("a".to_string())).unwrap();
+    assert_eq!(val.to_integer(), Some(1));
+
+    // {1: 2, -1: 3, h'01': 4}
+    let text = "a301022003410104";
+    let val = Cbor::from_base(text, Base::Base16Lower).unwrap();
+    let err = Basic::try_from(val.clone()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::FailConvert);
+
+    let doc = Basic::from_cbor(val, false).unwrap();
+    assert_eq!(doc.get(&Key::Offset(1)).unwrap().to_integer(), Some(2));
+    assert_eq!(doc.get(&Key::NegInt(-1)).unwrap().to_integer(), Some(3));
+    assert_eq!(doc.get(&Key::Bytes(vec![1])).unwrap().to_integer(), Some(4));
+    // negative keys do not index from the end, like they do for lists.
+    assert!(doc.get(&Key::NegOffset(-1)).is_err());
+
+    // non-text keys round-trip, but are not valid DAG-CBOR.
+    let buf = doc.to_cbor_bytes().unwrap();
+    assert_eq!(Base::Base16Lower.encode(&buf), text);
+    let err = doc.to_dag_cbor_bytes().unwrap_err();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+}
+
+#[test]
+fn test_from_cbor_ordered() {
+    // {"b": 1, "a": 2}, deliberately not in canonical order.
+    let text = "a2616201616102";
+    let keys = |doc: &dyn Node| -> Vec<Key> { doc.iter_source_entries().map(|(k, _)| k).collect() };
+    let (a, b) = (Key::Text("a".to_string()), Key::Text("b".to_string()));
+
+    let val = Cbor::from_base(text, Base::Base16Lower).unwrap();
+    let doc = Basic::from_cbor_ordered(val, true).unwrap();
+    assert_eq!(keys(&doc), vec![b.clone(), a.clone()]);
+    assert_eq!(doc.keys(), vec![a.clone(), b.clone()]);
+    assert_eq!(doc.get(&a).unwrap().to_integer(), Some(2));
+    let bytes = doc.to_cbor_bytes().unwrap();
+    assert_eq!(bytes, vec![0xa2, 0x61, 0x61, 0x02, 0x61, 0x62, 0x01]);
+
+    // set and delete return a modified copy, leaving the source as is.
+    let c = Key::Text("c".to_string());
+    let copy = doc.set(&c, Box::new(Basic::Integer(3))).unwrap();
+    assert_eq!(copy.get(&c).unwrap().to_integer(), Some(3));
+    assert_eq!(keys(&*copy), vec![b.clone(), a.clone(), c.clone()]);
+    assert!(doc.get(&c).is_err());
+    let copy = copy.delete(&b).unwrap();
+    assert_eq!(copy.len(), Some(2));
+    assert!(copy.get(&b).is_err());
+    assert!(copy.delete(&b).is_err());
+    assert_eq!(doc.len(), Some(2));
+
+    let val = Cbor::from_base(text, Base::Base16Lower).unwrap();
+    let doc = Basic::try_from(val).unwrap();
+    assert_eq!(keys(&doc), vec![a, b]);
+}
+
+#[test]
+fn test_from_iter() {
+    let list: Basic = vec![10_i128, 20, 30].into_iter().map(Basic::from).collect();
+    assert_eq!(list.to_kind(), Kind::List);
+    assert_eq!(list.len(), Some(3));
+    assert_eq!(list.get(&Key::Offset(1)).unwrap().to_integer(), Some(20));
+    let items: Vec<i128> = list.iter().filter_map(|x| x.to_integer()).collect();
+    assert_eq!(items, vec![10, 20, 30]);
+
+    let map: Basic = vec![("b", Basic::from(true)), ("a", Basic::from("x"))]
+        .into_iter()
+        .map(|(k, v)| (Key::Text(k.to_string()), v))
+        .collect();
+    assert_eq!(map.to_kind(), Kind::Map);
+    let val = map.get(&Key::Text("b".to_string())).unwrap();
+    assert_eq!(val.to_bool(), Some(true));
+    let keys: Vec<Key> = map.iter_entries().map(|(k, _)| k).collect();
+    assert_eq!(
+        keys,
+        vec![Key::Text("a".to_string()), Key::Text("b".to_string())]
+    );
+}
+
+#[test]
+fn test_encoded_size() {
+    let long_text: String = (0..300).map(|_| 'x').collect();
+    let long_list: Basic = (0..30_i128).map(|x| Basic::from(x * 1000)).collect();
+    let docs = vec![
+        Basic::Null,
+        Basic::Integer(23),
+        Basic::Integer(70000),
+        Basic::Integer(-10),
+        Basic::Float(1.5),
+        Basic::from(long_text.as_str()),
+        Basic::Bytes(vec![1, 2, 3].into()),
+        Basic::Link(make_cid(b"one").into()),
+        long_list,
+        make_map(vec![
+            ("a", Basic::Integer(1)),
+            ("list", make_list(vec![Basic::Bool(true), Basic::Null])),
+            ("link", Basic::Link(make_cid(b"two").into())),
+        ]),
+    ];
+    for doc in docs.iter() {
+        let n = doc.to_cbor_bytes().unwrap().len();
+        assert_eq!(doc.encoded_size().unwrap(), n);
+    }
+
+    let counts: Vec<usize> = docs.iter().map(|doc| doc.node_count()).collect();
+    assert_eq!(counts, vec![1, 1, 1, 1, 1, 1, 1, 1, 31, 6]);
+
+    // non-text keys, sized same as they are encoded.
+    let doc: Basic = vec![
+        (Key::Bool(true), Basic::Null),
+        (Key::Offset(1000), Basic::Null),
+        (Key::NegInt(-300), Basic::Null),
+        (Key::Bytes(vec![1, 2, 3]), Basic::Null),
+        (Key::Keyable(Box::new(12345_u32)), Basic::Null),
+        (Key::Text(long_text.clone()), Basic::Null),
+    ]
+    .into_iter()
+    .collect();
+    let n = doc.to_cbor_bytes().unwrap().len();
+    assert_eq!(doc.encoded_size().unwrap(), n);
+
+    let doc: Basic = vec![(Key::NegOffset(-1), Basic::Null)]
+        .into_iter()
+        .collect();
+    assert!(doc.to_cbor_bytes().is_err());
+    assert!(doc.encoded_size().is_err());
+}
+
+#[test]
+fn test_basic_display() {
+    let cid = make_cid(b"one");
+    let refs: Vec<(Basic, String)> = vec![
+        (Basic::Null, "null".to_string()),
+        (Basic::Bool(true), "true".to_string()),
+        (Basic::Integer(5), "5".to_string()),
+        (Basic::Integer(-12), "-12".to_string()),
+        (Basic::Float(1.5), "1.5".to_string()),
+        (Basic::from("hello"), "hello".to_string()),
+        (
+            Basic::Bytes(vec![1, 2, 3].into()),
+            "<bytes len 3>".to_string(),
+        ),
+        (Basic::Link(cid.clone().into()), cid.to_text(None).unwrap()),
+        (
+            make_list(vec![Basic::Null, Basic::Null]),
+            "[list len 2]".to_string(),
+        ),
+        (
+            make_map(vec![("a", Basic::Null)]),
+            "{map len 1}".to_string(),
+        ),
+    ];
+    for (val, s) in refs.into_iter() {
+        assert_eq!(val.to_string(), s);
+    }
+}
+
+#[test]
+fn test_cmp_node() {
+    let mut nodes: Vec<Basic> = vec![
+        make_list(vec![Basic::Integer(1), Basic::Integer(2)]),
+        Basic::from("b"),
+        Basic::Integer(10),
+        make_list(vec![Basic::Integer(1)]),
+        Basic::from("a"),
+        Basic::Integer(-3),
+        Basic::Float(0.5),
+        Basic::Null,
+    ];
+    nodes.sort_by(|a, b| cmp_node(a, b));
+
+    let kinds: Vec<Kind> = nodes.iter().map(|n| n.to_kind()).collect();
+    let refs = vec![
+        Kind::Null,
+        Kind::Integer,
+        Kind::Integer,
+        Kind::Float,
+        Kind::Text,
+        Kind::Text,
+        Kind::List,
+        Kind::List,
+    ];
+    assert_eq!(kinds, refs);
+    assert_eq!(nodes[1].to_integer(), Some(-3));
+    assert_eq!(nodes[2].to_integer(), Some(10));
+    assert_eq!(nodes[4].to_string(), "a");
+    assert_eq!(nodes[6].len(), Some(1));
+    assert_eq!(nodes[7].len(), Some(2));
+
+    let (a, b) = (
+        make_map(vec![("a", Basic::Integer(1))]),
+        make_map(vec![("a", Basic::Integer(2))]),
+    );
+    assert_eq!(cmp_node(&a, &b), cmp::Ordering::Less);
+    assert_eq!(cmp_node(&a, &a.clone()), cmp::Ordering::Equal);
+}
+
+#[test]
+fn test_semantic_eq() {
+    let a = make_map(vec![("x", Basic::Integer(1)), ("y", Basic::from("b"))]);
+    // {"y": "b", "x": 1}, held in insertion order.
+    let val = Cbor::from_base("a261796162617801", Base::Base16Lower).unwrap();
+    let b = Basic::from_cbor_ordered(val, false).unwrap();
+    assert!(semantic_eq(&a, &b));
+
+    let a = make_list(vec![Basic::Integer(1), Basic::Integer(2)]);
+    let b = make_list(vec![Basic::Integer(2), Basic::Integer(1)]);
+    assert!(!semantic_eq(&a, &b));
+    assert!(semantic_eq(&a, &a.clone()));
+
+    assert!(!semantic_eq(&Basic::Float(-0.0), &Basic::Float(0.0)));
+    assert!(semantic_eq(
+        &Basic::Float(std::f64::NAN),
+        &Basic::Float(std::f64::NAN)
+    ));
+    assert!(!semantic_eq(&Basic::Integer(1), &Basic::Float(1.0)));
+}
+
+#[test]
+fn test_cmp_float() {
+    use std::f64::{INFINITY, NAN, NEG_INFINITY};
+
+    assert_eq!(cmp_float(-0.0, 0.0), cmp::Ordering::Less);
+    assert_eq!(cmp_float(0.0, -0.0), cmp::Ordering::Greater);
+    assert_eq!(cmp_float(NAN, NAN), cmp::Ordering::Equal);
+    assert_eq!(cmp_float(NAN, INFINITY), cmp::Ordering::Greater);
+    assert_eq!(cmp_float(-NAN, INFINITY), cmp::Ordering::Greater);
+
+    let mut vals = vec![NAN, 1.5, INFINITY, 0.0, -NAN, -0.0, NEG_INFINITY, -2.5];
+    vals.sort_by(|a, b| cmp_float(*a, *b));
+    let refs = vec![NEG_INFINITY, -2.5, -0.0, 0.0, 1.5, INFINITY];
+    for (val, r) in vals.iter().zip(refs.iter()) {
+        assert_eq!(val.to_bits(), r.to_bits());
+    }
+    assert!(vals[6].is_nan() && vals[7].is_nan());
+
+    let (a, b) = (Basic::Float(-0.0), Basic::Float(0.0));
+    assert_eq!(cmp_node(&a, &b), cmp::Ordering::Less);
+    let (a, b) = (Basic::Float(NAN), Basic::Float(INFINITY));
+    assert_eq!(cmp_node(&a, &b), cmp::Ordering::Greater);
+}
+
+#[test]
+fn test_from_cbor_duplicate_keys() {
+    // {"a": 1, "b": 3, "a": 2}
+    let text = "a3616101616203616102";
+    let a = Key::Text("a".to_string());
+
+    let val = Cbor::from_base(text, Base::Base16Lower).unwrap();
+    let err = Basic::try_from(val.clone()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::FailConvert);
+    assert!(err.to_string().contains("duplicate map key"), "{}", err);
+
+    let doc = Basic::from_cbor(val.clone(), false).unwrap();
+    assert_eq!(doc.len(), Some(2));
+    assert_eq!(doc.get(&a).unwrap().to_integer(), Some(2));
+
+    let doc = Basic::from_cbor_ordered(val, false).unwrap();
+    let keys: Vec<Key> = doc.iter_entries().map(|(k, _)| k).collect();
+    assert_eq!(keys, vec![a.clone(), Key::Text("b".to_string())]);
+    assert_eq!(doc.get(&a).unwrap().to_integer(), Some(2));
+}
+
+#[test]
+fn test_from_cbor_invalid_text() {
+    // text string carrying invalid UTF-8 bytes.
+    let val = Cbor::from_base("62fffe", Base::Base16Lower).unwrap();
+
+    let err = Basic::try_from(val.clone()).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::FailConvert);
+    assert!(err.source().is_some());
+
+    let doc = Basic::from_cbor(val, false).unwrap();
+    assert_eq!(doc.to_kind(), Kind::Bytes);
+    assert_eq!(doc.as_bytes(), Some(vec![0xff, 0xfe].as_slice()));
+
+    let val = Cbor::from_base("626162", Base::Base16Lower).unwrap();
+    let doc = Basic::from_cbor(val, false).unwrap();
+    assert_eq!(doc.as_string().unwrap().unwrap(), "ab");
+}
+
+#[test]
+fn test_get_key() {
+    let doc = make_map(vec![
+        ("a", make_list(vec![Basic::Integer(1), Basic::Integer(2)])),
+        ("b", Basic::Bool(true)),
+    ]);
+
+    assert_eq!(doc.get_key("b").unwrap().to_bool(), Some(true));
+    assert_eq!(doc.get_key("b".to_string()).unwrap().to_bool(), Some(true));
+
+    let list = doc.get_key("a").unwrap();
+    assert_eq!(list.get_key(1).unwrap().to_integer(), Some(2));
+    assert_eq!(list.get_key("0").unwrap().to_integer(), Some(1));
+
+    let err = list.get_key(2).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::IndexFail);
+    let err = doc.get_key("c").err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::IndexFail);
+}
+
+#[test]
+fn test_get_or() {
+    let doc = make_map(vec![
+        ("port", Basic::Integer(8080)),
+        (
+            "tls",
+            make_map(vec![(
+                "certs",
+                make_list(vec![Basic::from("a.pem"), Basic::from("b.pem")]),
+            )]),
+        ),
+    ]);
+    let default = Basic::Integer(80);
+
+    let port = doc.get_or(&Key::Text("port".to_string()), &default);
+    assert_eq!(port.to_integer(), Some(8080));
+    let val = doc.get_or(&Key::Text("timeout".to_string()), &default);
+    assert_eq!(val.to_integer(), Some(80));
+    let val = Basic::Null.get_or(&Key::Offset(0), &default);
+    assert_eq!(val.to_integer(), Some(80));
+
+    let val = doc.get_path_or("/tls/certs/1", &default);
+    assert_eq!(val.as_ffi_string(), Some("b.pem"));
+    let val = doc.get_path_or("tls/certs/2", &default);
+    assert_eq!(val.to_integer(), Some(80));
+    let val = doc.get_path_or("port/x", &default);
+    assert_eq!(val.to_integer(), Some(80));
+    assert_eq!(doc.get_path_or("", &default).to_kind(), Kind::Map);
+}
+
+#[test]
+fn test_byte_len() {
+    let text = Basic::from("héllo");
+    assert_eq!(text.byte_len(), Some(6));
+    assert_eq!(text.len(), None);
+
+    let bytes = Basic::Bytes(vec![1, 2, 3].into());
+    assert_eq!(bytes.byte_len(), Some(3));
+
+    let list = make_list(vec![Basic::from("ab"), Basic::from("cd")]);
+    assert_eq!(list.len(), Some(2));
+    assert_eq!(list.byte_len(), None);
+    assert_eq!(Basic::Integer(1000).byte_len(), None);
+}
+
+#[test]
+fn test_find() {
+    let (one, two) = (make_cid(b"one"), make_cid(b"two"));
+    let doc = make_map(vec![
+        ("a", make_list(vec![Basic::Integer(1), Basic::from("x")])),
+        (
+            "b",
+            make_list(vec![
+                Basic::Integer(2),
+                make_map(vec![("c", Basic::Link(one.clone().into()))]),
+            ]),
+        ),
+        ("d", Basic::Link(two.into())),
+    ]);
+
+    let (path, node) = doc.find(|n| n.to_kind() == Kind::Link).unwrap();
+    let refs = vec![
+        Key::Text("b".to_string()),
+        Key::Offset(1),
+        Key::Text("c".to_string()),
+    ];
+    assert_eq!(path, refs);
+    assert_eq!(node.as_link(), Some(&one));
+
+    let node: &dyn Node = &doc;
+    let (path, _) = node.find(|n| n.as_ffi_string() == Some("x")).unwrap();
+    assert_eq!(path, vec![Key::Text("a".to_string()), Key::Offset(1)]);
+
+    let (path, _) = doc.find(|n| n.to_kind() == Kind::Map).unwrap();
+    assert!(path.is_empty());
+    assert!(doc.find(|n| n.to_bool().is_some()).is_none());
+}
+
+#[test]
+fn test_as_link_lenient() {
+    let cid = make_cid(b"one");
+
+    let val = Basic::Bytes(cid.encode().unwrap().into());
+    assert!(val.as_link().is_none());
+    assert_eq!(val.as_link_lenient(), Some(cid.clone()));
+
+    let val = Basic::Link(cid.clone().into());
+    assert_eq!(val.as_link(), Some(&cid));
+    assert_eq!(val.as_link_lenient(), Some(cid.clone()));
+
+    let mut data = cid.encode().unwrap();
+    data.push(0);
+    assert!(Basic::Bytes(data.into()).as_link_lenient().is_none());
+    assert!(Basic::Bytes(b"not-a-cid".to_vec().into())
+        .as_link_lenient()
+        .is_none());
+    assert!(Basic::Integer(10).as_link_lenient().is_none());
+}
+
+#[test]
+fn test_as_string_offset() {
+    let mut data = b"hello world".to_vec();
+    data.extend_from_slice(&[0xff, 0x61]);
+    let err = Basic::Text(data.into()).as_string().unwrap().unwrap_err();
+    assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+    assert!(
+        err.to_string().contains("valid up to 11 of 13 bytes"),
+        "{}",
+        err
+    );
+
+    let src = err.source().unwrap();
+    let src = src.downcast_ref::<std::str::Utf8Error>().unwrap();
+    assert_eq!(src.valid_up_to(), 11);
+}
+
+#[test]
+fn test_try_from_basic() {
+    use std::convert::TryInto;
+
+    let node = Basic::Integer(-10);
+    let n: i64 = (&node).try_into().unwrap();
+    assert_eq!(n, -10);
+    assert!(u64::try_from(&node).is_err());
+
+    let node = Basic::Integer(u64::MAX as i128);
+    assert_eq!(u64::try_from(&node).unwrap(), u64::MAX);
+    let err = i64::try_from(&node).unwrap_err();
+    assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+
+    let node = Basic::Integer(u64::MAX as i128 + 1);
+    assert!(u64::try_from(&node).is_err());
+
+    assert_eq!(f64::try_from(&Basic::Float(1.5)).ok(), Some(1.5));
+    assert!(bool::try_from(&Basic::Bool(true)).unwrap());
+    assert_eq!(String::try_from(&Basic::from("hello")).unwrap(), "hello");
+    let bytes = Basic::Bytes(vec![1, 2, 3].into());
+    assert_eq!(Vec::<u8>::try_from(&bytes).unwrap(), vec![1, 2, 3]);
+
+    // same, through a trait object.
+    let node: &dyn Node = &bytes;
+    assert_eq!(Vec::<u8>::try_from(node).unwrap(), vec![1, 2, 3]);
+    assert!(String::try_from(node).is_err());
+
+    // kind mismatch.
+    let err = i64::try_from(&Basic::from("10")).unwrap_err();
+    assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+    assert!(f64::try_from(&Basic::Integer(1)).is_err());
+    assert!(bool::try_from(&Basic::Null).is_err());
+    assert!(String::try_from(&Basic::Text(vec![0xff].into())).is_err());
+}
+
+#[test]
+fn test_keys() {
+    let doc = make_map(vec![("b", Basic::Integer(2)), ("a", Basic::Integer(1))]);
+    let keys = vec![Key::Text("a".to_string()), Key::Text("b".to_string())];
+    assert_eq!(doc.keys(), keys);
+    assert!(doc.contains_key(&Key::Text("a".to_string())));
+    assert!(!doc.contains_key(&Key::Text("c".to_string())));
+
+    let doc = make_list(vec![Basic::Null, Basic::Bool(true), Basic::Integer(3)]);
+    let keys = vec![Key::Offset(0), Key::Offset(1), Key::Offset(2)];
+    assert_eq!(doc.keys(), keys);
+    assert!(doc.contains_key(&Key::Offset(2)));
+    assert!(!doc.contains_key(&Key::Offset(3)));
+
+    assert!(Basic::Integer(10).keys().is_empty());
+    assert!(!Basic::Integer(10).contains_key(&Key::Offset(0)));
+}
+
+#[test]
+fn test_node_send_sync() {
+    use std::{sync::Arc, thread};
+
+    let doc = make_map(vec![
+        (
+            "list",
+            make_list(vec![Basic::Integer(1), Basic::from("two")]),
+        ),
+        ("link", Basic::Link(make_cid(b"one").into())),
+    ]);
+    let bytes = doc.to_cbor_bytes().unwrap();
+
+    let mut r: &[u8] = &bytes;
+    let doc = Basic::try_from(Cbor::decode(&mut r).unwrap()).unwrap();
+    let handle = thread::spawn(move || doc.to_cbor_bytes().unwrap());
+    assert_eq!(handle.join().unwrap(), bytes);
+
+    let doc = Arc::new(make_list(vec![Basic::Integer(1), Basic::Integer(2)]));
+    let handles: Vec<thread::JoinHandle<usize>> = (0..4)
+        .map(|_| {
+            let doc = Arc::clone(&doc);
+            thread::spawn(move || doc.node_count())
+        })
+        .collect();
+    for handle in handles.into_iter() {
+        assert_eq!(handle.join().unwrap(), 3);
+    }
+}
+
+#[test]
+fn test_undefined() {
+    // [undefined, 1]
+    let bytes = vec![0x82, 0xf7, 0x01];
+
+    let val = Cbor::decode(&mut bytes.as_slice()).unwrap();
+    let err = Basic::from_cbor(val, true).unwrap_err();
+    assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+
+    let val = Cbor::decode(&mut bytes.as_slice()).unwrap();
+    let doc = Basic::from_cbor(val, false).unwrap();
+    let item = doc.get(&Key::Offset(0)).unwrap();
+    assert_eq!(item.to_kind(), Kind::Undefined);
+    assert!(!item.is_null());
+
+    assert_eq!(doc.to_cbor_bytes().unwrap(), bytes);
+    assert_eq!(doc.encoded_size().unwrap(), bytes.len());
+    let err = doc.to_dag_cbor_bytes().unwrap_err();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+}
+
+#[test]
+fn test_canonical_key_order() {
+    let texts = vec!["bb", "a", "aaa", "b", "", "ab", "ba", "zz"];
+    let mut keys: Vec<Key> = texts.iter().map(|s| Key::Text(s.to_string())).collect();
+    canonical_key_order(&mut keys);
+
+    let refs: Vec<Key> = vec!["", "a", "b", "ab", "ba", "bb", "zz", "aaa"]
+        .into_iter()
+        .map(|s| Key::Text(s.to_string()))
+        .collect();
+    assert_eq!(keys, refs);
+
+    // same order as the encoder.
+    let doc: Basic = texts
+        .iter()
+        .map(|s| (Key::Text(s.to_string()), Basic::Null))
+        .collect();
+    let val = Cbor::try_from(&doc as &dyn Node).unwrap();
+    let mut buf = vec![];
+    val.encode(&mut buf).unwrap();
+    let val = Cbor::decode(&mut buf.as_slice()).unwrap();
+    let encoded: Vec<Key> = val.map_iter().unwrap().map(|(k, _)| k.clone()).collect();
+    assert_eq!(encoded, refs);
+}
+
+#[test]
+fn test_text_offset() {
+    let doc = make_list(vec![Basic::Integer(1), Basic::Integer(2)]);
+    assert_eq!(
+        doc.get(&Key::Text("1".to_string())).unwrap().to_integer(),
+        Some(2)
+    );
+
+    let too_big = format!("{}0", usize::MAX);
+    let testcases = vec![
+        ("abc", "offset \"abc\" is not a number"),
+        ("", "offset \"\" is not a number"),
+        ("-1", "negative offset -1 not allowed"),
+        (too_big.as_str(), "exceeds"),
+    ];
+    for (key, msg) in testcases.into_iter() {
+        let err = doc.get(&Key::Text(key.to_string())).err().unwrap();
+        assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+        assert!(err.to_string().contains(msg), "{} {}", key, err);
+    }
+
+    let mut doc = doc;
+    let err = doc.get_mut(&Key::Text("-2".to_string())).err().unwrap();
+    assert!(err.to_string().contains("negative offset -2"), "{}", err);
+    let value = Box::new(Basic::Null);
+    let err = doc.insert(Key::Text("x".to_string()), value).err().unwrap();
+    assert!(err.to_string().contains("is not a number"), "{}", err);
+}
+
+#[test]
+fn test_basic_footprint() {
+    use std::mem::size_of;
+
+    // with text, bytes and links boxed, Basic is as wide as its integer
+    // payload plus discriminant, an inline Cid alone is wider.
+    let width = size_of::<Basic>();
+    assert!(width <= 2 * size_of::<i128>(), "{}", width);
+    assert!(width < size_of::<Cid>(), "{} {}", width, size_of::<Cid>());
+
+    // heap held by decoded text, Vec's capacity while in Cbor and boxed
+    // slice's length once in Basic.
+    let texts: Vec<String> = (0..100).map(|i| format!("text-{}", i)).collect();
+    let doc: Basic = texts
+        .iter()
+        .map(|text| Basic::from(text.as_str()))
+        .collect();
+    let bytes = doc.to_cbor_bytes().unwrap();
+    let val = Cbor::decode(&mut bytes.as_slice()).unwrap();
+    let before: usize = match &val {
+        Cbor::Major4(_, items) => items
+            .iter()
+            .map(|item| match item {
+                Cbor::Major3(_, text) => text.capacity(),
+                _ => unreachable!(),
+            })
+            .sum(),
+        _ => unreachable!(),
+    };
+    let doc = Basic::try_from(val).unwrap();
+    let after: usize = doc
+        .iter()
+        .map(|item| item.as_string().unwrap().unwrap().len())
+        .sum();
+    assert_eq!(after, texts.iter().map(|text| text.len()).sum::<usize>());
+    assert!(after <= before, "{} {}", after, before);
+
+    // excess capacity is dropped on conversion, boxed slice's length is
+    // its allocation.
+    let mut text = String::with_capacity(1024);
+    text.push_str("abc");
+    match Basic::from(text) {
+        Basic::Text(val) => assert_eq!(val.len(), 3),
+        _ => unreachable!(),
+    }
+
+    let doc: Basic = (0..100)
+        .map(|i| Basic::from(format!("text-{}", i)))
+        .chain((0..100).map(|i| Basic::Bytes(vec![i as u8; i].into())))
+        .collect();
+    let bytes = doc.to_cbor_bytes().unwrap();
+    let doc = Basic::try_from(Cbor::decode(&mut bytes.as_slice()).unwrap()).unwrap();
+    for (i, item) in doc.iter().enumerate() {
+        match i {
+            i if i < 100 => {
+                let text = format!("text-{}", i);
+                assert_eq!(item.as_string().unwrap().unwrap(), text);
+                assert_eq!(item.as_ffi_string().unwrap(), text);
+            }
+            i => assert_eq!(
+                item.as_bytes().unwrap(),
+                vec![(i - 100) as u8; i - 100].as_slice()
+            ),
+        }
+    }
+    assert_eq!(doc.to_cbor_bytes().unwrap(), bytes);
+
+    // text constructed from invalid UTF-8 has no `&str` view.
+    let val = Basic::Text(vec![0x61, 0xff].into());
+    assert_eq!(val.as_ffi_string(), None);
+    assert!(val.as_string().unwrap().is_err());
+    assert_eq!(val.as_text_bytes(), Some(&[0x61, 0xff][..]));
+}
+
+#[test]
+fn test_basic_partial_eq() {
+    assert_eq!(Basic::from(5), 5);
+    assert_ne!(Basic::from(5), 6);
+    assert_ne!(Basic::from(5.0), 5);
+    assert_ne!(Basic::from("5"), 5);
+
+    assert_eq!(Basic::from("hello"), "hello");
+    assert_ne!(Basic::from("hello"), "world");
+    assert_ne!(Basic::Bytes(b"hello".to_vec().into()), "hello");
+    assert_ne!(Basic::Null, "null");
+
+    assert_eq!(Basic::from(true), true);
+    assert_ne!(Basic::from(true), false);
+    assert_ne!(Basic::from(1), true);
+    assert_ne!(Basic::from("true"), true);
+
+    assert_eq!(Basic::from(1.5), 1.5);
+    assert_ne!(Basic::from(1.5), 2.5);
+    assert_ne!(Basic::from(f64::NAN), f64::NAN);
+    assert_ne!(Basic::from(1), 1.0);
+    assert_ne!(Basic::from(false), 0.0);
+}
+
+#[test]
+fn test_basic_debug() {
+    let cid = make_cid(b"one");
+    let doc = make_map(vec![
+        ("a", make_list(vec![Basic::from(1), Basic::from("x y")])),
+        ("b", Basic::Bytes(vec![1, 2].into())),
+        ("c", Basic::Link(cid.clone().into())),
+        ("d", Basic::Null),
+    ]);
+    let refs = format!(
+        r#"{{key-str-a: [Integer(1), Text("x y")], key-str-b: Bytes([1, 2]), key-str-c: Link({}), key-str-d: Null}}"#,
+        cid
+    );
+    assert_eq!(format!("{:?}", doc), refs);
+    assert_eq!(doc.to_string(), "{map len 4}");
+
+    assert_eq!(format!("{:?}", Basic::from(1.5)), "Float(1.5)");
+    assert_eq!(format!("{:?}", Basic::from(true)), "Bool(true)");
+    assert_eq!(format!("{:?}", Basic::Undefined), "Undefined");
+}
+
+#[test]
+fn test_to_basic() {
+    let doc = make_map(vec![
+        ("null", Basic::Null),
+        ("bool", Basic::from(true)),
+        ("int", Basic::from(-10)),
+        ("float", Basic::from(1.5)),
+        ("text", Basic::from("hello")),
+        ("bytes", Basic::Bytes(vec![1, 2, 3].into())),
+        ("link", Basic::Link(make_cid(b"hello").into())),
+        ("list", make_list(vec![Basic::from(1), make_list(vec![])])),
+    ]);
+    let bytes = doc.to_cbor_bytes().unwrap();
+
+    // decoded from cbor, maps backed by Vec in source order.
+    let val = Cbor::decode(&mut bytes.as_slice()).unwrap();
+    let decoded = Basic::from_cbor_ordered(val, true).unwrap();
+    let a = decoded.to_basic();
+
+    // borrowed view over a Vec backed map.
+    let entries: Vec<(Key, Box<dyn Node>)> = doc
+        .iter_entries()
+        .map(|(key, value)| (key, value.clone_box()))
+        .collect();
+    let view: &dyn Node = &entries;
+    let b = view.to_basic();
+
+    assert_eq!(cmp_node(&a, &doc), cmp::Ordering::Equal);
+    assert_eq!(cmp_node(&a, &b), cmp::Ordering::Equal);
+    assert_eq!(a.to_cbor_bytes().unwrap(), bytes);
+    assert_eq!(b.to_cbor_bytes().unwrap(), bytes);
+
+    // materialized tree is owned, and can be mutated.
+    let mut b = b;
+    b.insert(Key::Text("new".to_string()), Box::new(Basic::Null))
+        .unwrap();
+    assert_eq!(b.len(), Some(9));
+    assert_eq!(view.len(), Some(8));
+
+    assert_eq!(Basic::from(10).to_basic(), 10);
+    assert_eq!(Basic::from("x").to_basic(), "x");
+
+    // text with invalid UTF-8, as decoded in lenient mode, is copied,
+    // compared and encoded as raw bytes.
+    let val = Basic::Text(vec![0x61, 0xff].into());
+    let copy = val.to_basic();
+    assert_eq!(copy.as_text_bytes(), Some(&[0x61, 0xff][..]));
+    assert_eq!(copy.byte_len(), Some(2));
+    assert_eq!(cmp_node(&val, &copy), cmp::Ordering::Equal);
+    let other = Basic::Text(vec![0x61, 0xfe].into());
+    assert_eq!(cmp_node(&val, &other), cmp::Ordering::Greater);
+    assert!(semantic_eq(&val, &copy));
+    assert_eq!(
+        val.normalize().unwrap().as_text_bytes(),
+        val.as_text_bytes()
+    );
+    assert_eq!(val.encoded_size().unwrap(), 3);
+    let bytes = val.to_cbor_bytes().unwrap();
+    assert_eq!(Base::Base16Lower.encode(&bytes), "6261ff");
+}
+
+#[test]
+fn test_iter_order() {
+    let entries = vec![
+        ("z", Basic::Link(make_cid(b"z").into())),
+        (
+            "aa",
+            make_list(vec![Basic::Link(make_cid(b"0").into()), Basic::from(1)]),
+        ),
+        ("b", Basic::Link(make_cid(b"b").into())),
+    ];
+
+    // built as BTreeMap, insertion ordered Vec, via FromIterator, and
+    // decoded from non-canonical cbor, both key ordered and insertion
+    // ordered.
+    let doc1 = make_map(entries.clone());
+    let doc2 = {
+        let map: Vec<(Key, Box<dyn Node>)> = entries
+            .iter()
+            .map(|(k, v)| (Key::Text(k.to_string()), v.clone_box()))
+            .collect();
+        Basic::Map(Box::new(map))
+    };
+    let doc3: Basic = entries
+        .iter()
+        .rev()
+        .map(|(k, v)| (Key::Text(k.to_string()), v.clone()))
+        .collect();
+    let (doc4, doc5) = {
+        let mut dict: Vec<(Key, Cbor)> = vec![];
+        for (k, v) in entries.iter() {
+            dict.push((
+                Key::Text(k.to_string()),
+                Cbor::try_from(v as &dyn Node).unwrap(),
+            ));
+        }
+        let val = Cbor::Major5((dict.len() as u64).into(), dict);
+        (
+            Basic::from_cbor(val.clone(), true).unwrap(),
+            Basic::from_cbor_ordered(val, true).unwrap(),
+        )
+    };
+
+    let paths = |doc: &Basic| {
+        let mut paths = vec![];
+        doc.walk(&mut |path, _| paths.push(path.to_vec()));
+        paths
+    };
+    let keys = vec![
+        Key::Text("aa".to_string()),
+        Key::Text("b".to_string()),
+        Key::Text("z".to_string()),
+    ];
+    for doc in [doc1.clone(), doc2.clone(), doc3, doc4, doc5.clone()].iter() {
+        assert_eq!(doc.keys(), keys);
+        assert_eq!(paths(doc), paths(&doc1));
+        assert_eq!(doc.links(), doc1.links());
+        let vals: Vec<Kind> = doc.iter().map(|v| v.to_kind()).collect();
+        assert_eq!(vals, vec![Kind::List, Kind::Link, Kind::Link]);
+        assert!(semantic_eq(doc, &doc1));
+    }
+
+    // insertion ordered backing still holds its entries in source order,
+    // however it was built.
+    let source = vec![keys[2].clone(), keys[0].clone(), keys[1].clone()];
+    for doc in [doc2, doc5].iter() {
+        let keys: Vec<Key> = doc.iter_source_entries().map(|(k, _)| k).collect();
+        assert_eq!(keys, source);
+    }
+    let keys: Vec<Key> = doc1.iter_source_entries().map(|(k, _)| k).collect();
+    assert_eq!(keys, doc1.keys());
+
+    let list = doc1.get(&keys[0]).unwrap();
+    let offs: Vec<Key> = list.iter_entries().map(|(k, _)| k).collect();
+    assert_eq!(offs, vec![Key::Offset(0), Key::Offset(1)]);
+}
+
+#[test]
+fn test_from_cbor_infallible() {
+    let infallible = vec![
+        "00",
+        "17",
+        "1818",
+        "1bffffffffffffffff",
+        "20",
+        "3bffffffffffffffff",
+        "40",
+        "43010203",
+        "60",
+        "6568656c6c6f",
+        "f4",
+        "f5",
+        "f6",
+        "fb3ff8000000000000",
+        "fb7ff8000000000000",
+    ];
+    let mut items: Vec<Cbor> = infallible
+        .into_iter()
+        .map(|text| Cbor::from_base(text, Base::Base16Lower).unwrap())
+        .collect();
+    items.push(Cbor::try_from(&Basic::Link(make_cid(b"hello").into()) as &dyn Node).unwrap());
+
+    for val in items.into_iter() {
+        let text = val.to_base(Base::Base16Lower).unwrap();
+        let a = Basic::from_cbor(val.clone(), true).unwrap();
+        let b = Basic::from_cbor_infallible(val).ok().unwrap();
+        assert_eq!(
+            a.to_cbor_bytes().unwrap(),
+            b.to_cbor_bytes().unwrap(),
+            "{}",
+            text
+        );
+    }
+
+    // lists, maps, undefined, 16-bit float and invalid text are handed back.
+    for text in ["80", "a0", "f7", "f93c00", "62fffe"].iter() {
+        let val = Cbor::from_base(text, Base::Base16Lower).unwrap();
+        let val = Basic::from_cbor_infallible(val).err().unwrap();
+        assert_eq!(val.to_base(Base::Base16Lower).unwrap(), *text);
+    }
+}
+
+#[test]
+fn test_resolve_path() {
+    let leaf = make_map(vec![
+        ("b", Basic::from(42)),
+        ("c", make_list(vec![Basic::from("x")])),
+    ]);
+    let cid = make_cid(&leaf.to_cbor_bytes().unwrap());
+    let root = make_map(vec![(
+        "a",
+        make_map(vec![
+            ("link", Basic::Link(cid.clone().into())),
+            ("n", Basic::from(1)),
+        ]),
+    )]);
+
+    let mut fetched = vec![];
+    let mut fetch = |c: &Cid| {
+        fetched.push(c.clone());
+        match c == &cid {
+            true => Ok(leaf.clone()),
+            false => err_at!(IndexFail, msg: "missing block"),
+        }
+    };
+    assert_eq!(root.resolve_path("a/link/b", &mut fetch).unwrap(), 42);
+    assert_eq!(root.resolve_path("/a/link/c/0", &mut fetch).unwrap(), "x");
+    assert_eq!(fetched, vec![cid.clone(), cid.clone()]);
+
+    // within block, and a trailing link is not fetched.
+    let no_fetch = |_: &Cid| -> Result<Basic> { panic!("unexpected fetch") };
+    assert_eq!(root.resolve_path("a/n", no_fetch).unwrap(), 1);
+    let val = root.resolve_path("a/link", no_fetch).unwrap();
+    assert_eq!(val.as_link(), Some(&cid));
+    assert_eq!(root.resolve_path("", no_fetch).unwrap().len(), Some(1));
+
+    let err = root.resolve_path("a/missing", no_fetch).unwrap_err();
+    assert!(matches!(err, Error::IndexFail(..)), "{}", err);
+    let err = root.resolve_path("a/n/x", no_fetch).unwrap_err();
+    assert!(matches!(err, Error::IndexFail(..)), "{}", err);
+
+    let other = make_map(vec![("link", Basic::Link(make_cid(b"other").into()))]);
+    let err = other.resolve_path("link/b", |_: &Cid| err_at!(IndexFail, msg: "missing block"));
+    assert!(matches!(err.unwrap_err(), Error::IndexFail(..)));
+
+    // blocks linking to each other, A -> B -> A, are followed only up to
+    // the hop limit.
+    let (a, b) = (make_cid(b"a"), make_cid(b"b"));
+    let mut hops = 0;
+    let cycle = |c: &Cid| {
+        hops += 1;
+        match c == &a {
+            true => Ok(Basic::Link(b.clone().into())),
+            false => Ok(Basic::Link(a.clone().into())),
+        }
+    };
+    let err = Basic::Link(a.clone().into())
+        .resolve_path("x", cycle)
+        .unwrap_err();
+    assert!(matches!(err, Error::DepthExceeded(..)), "{}", err);
+    assert_eq!(hops, LINK_HOP_LIMIT);
+}
+
+#[test]
+fn test_encode_with() {
+    let doc = make_map(vec![("z", Basic::from(1.5)), ("aa", Basic::from(1))]);
+
+    let mut buf = vec![];
+    let n = doc
+        .encode_with(&EncodeOptions::dag_cbor(), &mut buf)
+        .unwrap();
+    assert_eq!(n, buf.len());
+    assert_eq!(buf, doc.to_dag_cbor_bytes().unwrap());
+    // {"z": 1.5, "aa": 1}, keys in canonical order and 64-bit float.
+    assert_eq!(
+        Base::Base16Lower.encode(&buf),
+        "a2617afb3ff800000000000062616101"
+    );
+
+    // {"aa": 1, "z": 1.5}, keys in iteration order and 16-bit float.
+    let mut buf = vec![];
+    doc.encode_with(&EncodeOptions::default(), &mut buf)
+        .unwrap();
+    assert_eq!(Base::Base16Lower.encode(&buf), "a262616101617af93e00");
+
+    let doc = make_list(vec![Basic::from(f64::NAN)]);
+    let err = doc
+        .encode_with(&EncodeOptions::dag_cbor(), &mut vec![])
+        .unwrap_err();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+    let mut buf = vec![];
+    doc.encode_with(&EncodeOptions::default(), &mut buf)
+        .unwrap();
+    assert_eq!(Base::Base16Lower.encode(&buf), "81fb7ff8000000000000");
+
+    let doc = make_list(vec![Basic::Undefined]);
+    let err = doc
+        .encode_with(&EncodeOptions::dag_cbor(), &mut vec![])
+        .unwrap_err();
+    assert!(matches!(err, Error::FailCbor(..)), "{}", err);
+    let mut buf = vec![];
+    doc.encode_with(&EncodeOptions::default(), &mut buf)
+        .unwrap();
+    assert_eq!(Base::Base16Lower.encode(&buf), "81f7");
+}
+
+#[test]
+fn test_error_location() {
+    let err = Basic::Integer(10).get(&Key::Offset(0)).err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::IndexFail);
+    assert!(
+        err.location().starts_with("src/ipld/kind.rs:"),
+        "{}",
+        err.location()
+    );
+    assert_eq!(err.message(), "cannot index Int");
+
+    let text = err.to_string();
+    assert!(text.starts_with(err.location()), "{}", text);
+    assert!(text.contains("IndexFail"), "{}", text);
+    assert!(text.ends_with(err.message()), "{}", text);
+}
+
+#[test]
+fn test_get_many() {
+    let doc = make_map(vec![
+        ("id", Basic::from(7)),
+        ("name", Basic::from("alice")),
+        ("active", Basic::from(true)),
+        ("score", Basic::from(1.5)),
+    ]);
+    let key = |k: &str| Key::Text(k.to_string());
+
+    let keys = vec![key("name"), key("id"), key("active")];
+    let vals = doc.get_many(&keys).unwrap();
+    assert_eq!(vals.len(), 3);
+    assert_eq!(vals[0].as_ffi_string(), Some("alice"));
+    assert_eq!(vals[1].to_integer(), Some(7));
+    assert_eq!(vals[2].to_bool(), Some(true));
+
+    let keys = vec![key("name"), key("missing"), key("id")];
+    let err = doc.get_many(&keys).err().unwrap();
+    assert!(matches!(err, Error::IndexFail(..)), "{}", err);
+    assert!(err.to_string().contains("missing"), "{}", err);
+
+    let vals = doc.get_many_opt(&keys);
+    assert_eq!(vals[0].and_then(|v| v.as_ffi_string()), Some("alice"));
+    assert!(vals[1].is_none());
+    assert_eq!(vals[2].and_then(|v| v.to_integer()), Some(7));
+
+    let list = make_list(vec![Basic::from(1), Basic::from(2)]);
+    let vals = list.get_many(&[Key::Offset(1), Key::Offset(0)]).unwrap();
+    assert_eq!(vals[0].to_integer(), Some(2));
+    assert!(Basic::Null.get_many(&[key("x")]).is_err());
+    assert!(Basic::Null.get_many(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn test_to_number() {
+    assert_eq!(Basic::from(1.5).to_number(), Some(1.5));
+    assert_eq!(Basic::from(42).to_number(), Some(42.0));
+    assert_eq!(Basic::from(-42).to_number(), Some(-42.0));
+
+    // exact powers of two, beyond 2^53, are still lossless.
+    assert_eq!(Basic::from(1_i128 << 60).to_number(), Some(2_f64.powi(60)));
+    assert_eq!(Basic::from(i128::MIN).to_number(), Some(-(2_f64.powi(127))));
+
+    assert_eq!(Basic::from((1_i128 << 53) + 1).to_number(), None);
+    assert_eq!(Basic::from(u64::MAX as i128).to_number(), None);
+    assert_eq!(Basic::from(i128::MAX).to_number(), None);
+
+    assert_eq!(Basic::from("1").to_number(), None);
+    assert_eq!(Basic::Null.to_number(), None);
+}
+
+#[test]
+fn test_bytes_hex_base64() {
+    let val = Basic::from_hex("DEADbeef00").unwrap();
+    assert_eq!(val.as_bytes(), Some(&[0xde, 0xad, 0xbe, 0xef, 0x00][..]));
+    assert_eq!(val.as_hex().unwrap(), "deadbeef00");
+    let val = Basic::from_hex(&val.as_hex().unwrap()).unwrap();
+    assert_eq!(val.as_hex().unwrap(), "deadbeef00");
+    assert_eq!(Basic::from_hex("").unwrap().as_bytes(), Some(&[][..]));
+    assert_eq!(Basic::from("deadbeef").as_hex(), None);
+
+    for text in ["abc", "zz", "0x00"].iter() {
+        let err = Basic::from_hex(text).unwrap_err();
+        assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+    }
+
+    // no padding, one and two padding characters, with and without it.
+    let items = [
+        ("hello", "aGVsbG8="),
+        ("hi", "aGk="),
+        ("abc", "YWJj"),
+        ("", ""),
+    ];
+    for (data, text) in items.iter() {
+        let val = Basic::from_base64(text).unwrap();
+        assert_eq!(val.as_bytes(), Some(data.as_bytes()));
+        let val = Basic::from_base64(text.trim_end_matches('=')).unwrap();
+        assert_eq!(val.as_bytes(), Some(data.as_bytes()));
+        assert_eq!(Base::Base64Pad.encode(val.as_bytes().unwrap()), *text);
+    }
+
+    for text in ["aGVsbG8==", "a", "!!!!", "aGk=aGk="].iter() {
+        let err = Basic::from_base64(text).unwrap_err();
+        assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+    }
+}
+
+#[test]
+fn test_into_iter() {
+    let list = make_list(vec![Basic::from(1), Basic::from(2), Basic::from(3)]);
+    let mut acc = vec![];
+    for item in &list {
+        acc.push(item.to_integer().unwrap());
+    }
+    assert_eq!(acc, vec![1, 2, 3]);
+
+    let map = make_map(vec![("b", Basic::from(2)), ("a", Basic::from(1))]);
+    let iter = IntoIterator::into_iter(&map);
+    let vals: Vec<i128> = iter.filter_map(|v| v.to_integer()).collect();
+    assert_eq!(vals, vec![1, 2]);
+
+    let mut n = 0;
+    for _ in &Basic::Integer(10) {
+        n += 1;
+    }
+    assert_eq!(n, 0);
+}
+
+#[test]
+fn test_bignum() {
+    let decode = |text: &str| Cbor::from_base(text, Base::Base16Lower).unwrap();
+
+    let val = Basic::try_from(decode("1bffffffffffffffff")).unwrap();
+    assert_eq!(val.to_integer(), Some(u64::MAX as i128));
+    let val = Basic::try_from(decode("3bffffffffffffffff")).unwrap();
+    assert_eq!(val.to_integer(), Some(-1 - (u64::MAX as i128)));
+
+    // 2^64 and -1 - 2^64, beyond 64-bit major0/major1.
+    let val = Basic::from_cbor(decode("c249010000000000000000"), false).unwrap();
+    assert_eq!(val.to_integer(), Some(1_i128 << 64));
+    let val = Basic::from_cbor(decode("c349010000000000000000"), false).unwrap();
+    assert_eq!(val.to_integer(), Some(-1 - (1_i128 << 64)));
+    // leading zeros are not part of the magnitude.
+    let val = Basic::from_cbor(decode("c2420001"), false).unwrap();
+    assert_eq!(val.to_integer(), Some(1));
+
+    let err = Basic::try_from(decode("c249010000000000000000")).unwrap_err();
+    assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+
+    // 2^128, beyond i128, errors by default and clamps when saturating.
+    let text = "c2510100000000000000000000000000000000";
+    let err = Basic::from_cbor(decode(text), false).unwrap_err();
+    assert!(matches!(err, Error::FailConvert(..)), "{}", err);
+    let val = Basic::from_cbor_saturating(decode(text), false).unwrap();
+    assert_eq!(val.to_integer(), Some(i128::MAX));
+    let text = "c3510100000000000000000000000000000000";
+    let val = Basic::from_cbor_saturating(decode(text), false).unwrap();
+    assert_eq!(val.to_integer(), Some(i128::MIN));
+
+    // -1 - (2^127 - 1) is i128::MIN, exactly representable.
+    let val = Basic::from_cbor(decode("c3507fffffffffffffffffffffffffffffff"), false).unwrap();
+    assert_eq!(val.to_integer(), Some(i128::MIN));
+
+    assert_eq!(
+        decode("c2420001").to_base(Base::Base16Lower).unwrap(),
+        "c2420001"
+    );
+}
+
+#[test]
+fn test_kind_name() {
+    let err = Basic::from("x").get(&Key::Offset(0)).err().unwrap();
+    assert!(matches!(err, Error::IndexFail(..)), "{}", err);
+    assert_eq!(err.message(), "cannot index String");
+
+    let err = Basic::Bytes(vec![1].into())
+        .delete(&Key::Offset(0))
+        .err()
+        .unwrap();
+    assert_eq!(err.message(), "cannot index Bytes");
+
+    let mut doc = make_map(vec![("a", Basic::Float(1.5))]);
+    let err = doc.set_path("a/b/c", Basic::Null, true).unwrap_err();
+    assert!(err.message().starts_with("Float at"), "{}", err);
+
+    let node: &dyn Node = &Basic::Bool(true);
+    let err = i64::try_from(node).unwrap_err();
+    assert_eq!(err.message(), "Bool is not an integer");
+
+    let kinds = vec![
+        (Basic::Null, "Null"),
+        (Basic::Integer(1), "Int"),
+        (Basic::from("x"), "String"),
+        (Basic::Link(make_cid(b"x").into()), "Link"),
+        (make_list(vec![]), "List"),
+        (make_map(vec![]), "Map"),
+    ];
+    for (val, name) in kinds.into_iter() {
+        assert_eq!(val.kind_name(), name);
+        assert_eq!(val.to_kind().to_name(), name);
+    }
+}
@@ -1,7 +1,24 @@
+//! Module implement IPLD, data-model, codecs and blocks.
+//!
+//! The data-model in [kind] and the [cbor] value type are written against
+//! `core` and `alloc`, and are available without the `std` feature. The
+//! `std::io` based encoders and decoders, along with the other modules,
+//! need `std`.
+
+#[cfg(feature = "std")]
 pub mod block;
+#[cfg(feature = "std")]
+pub mod car;
 pub mod cbor;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod json;
 pub mod kind;
+#[cfg(feature = "std")]
 pub mod schema;
+#[cfg(feature = "std")]
+pub mod selector;
 
 //#[cfg(test)]
 //#[path = "sgrammar_test.rs"]
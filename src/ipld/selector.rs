@@ -0,0 +1,95 @@
+//! Module implement a subset of IPLD selectors, for partial traversal.
+//!
+//! A [Selector] is evaluated against a node, typically a [Basic] tree, and
+//! the nodes that it selects are returned along with their path from the
+//! root. _Refer [selectors] spec for details_, only the following are
+//! supported:
+//!
+//! * [Selector::Matcher], select the current node.
+//! * [Selector::ExploreAll], explore all the children of current node.
+//! * [Selector::ExploreFields], explore the named fields of a map.
+//! * [Selector::ExploreIndex], explore an entry within a list.
+//! * [Selector::ExploreRecursive], select the current node and all its
+//!   descendants, up to a depth limit.
+//!
+//! [Basic]: crate::ipld::kind::Basic
+//! [selectors]: https://ipld.io/specs/selectors/
+
+use crate::ipld::kind::{Key, Kind, Node};
+
+/// Selector to evaluate against a node.
+#[derive(Clone, Debug)]
+pub enum Selector {
+    /// Select the current node.
+    Matcher,
+    /// Apply `next` on every child of the current node.
+    ExploreAll { next: Box<Selector> },
+    /// Apply the paired selector on each named field, if current node is a
+    /// map. Missing fields are skipped.
+    ExploreFields { fields: Vec<(String, Selector)> },
+    /// Apply `next` on the entry at `index`, if current node is a list.
+    ExploreIndex { index: usize, next: Box<Selector> },
+    /// Select the current node, and its descendants up to `limit` levels
+    /// below the current node, in pre-order.
+    ExploreRecursive { limit: usize },
+}
+
+/// Evaluate selector `sel` against `root` and return the selected nodes,
+/// along with their path from `root`.
+pub fn select<'a>(root: &'a dyn Node, sel: &Selector) -> Vec<(Vec<Key>, &'a dyn Node)> {
+    let mut acc = vec![];
+    do_select(root, sel, &mut vec![], &mut acc);
+    acc
+}
+
+fn do_select<'a>(
+    node: &'a dyn Node,
+    sel: &Selector,
+    path: &mut Vec<Key>,
+    acc: &mut Vec<(Vec<Key>, &'a dyn Node)>,
+) {
+    match sel {
+        Selector::Matcher => acc.push((path.clone(), node)),
+        Selector::ExploreAll { next } => {
+            for (key, child) in node.iter_entries() {
+                path.push(key);
+                do_select(child, next, path, acc);
+                path.pop();
+            }
+        }
+        Selector::ExploreFields { fields } if node.to_kind() == Kind::Map => {
+            for (name, next) in fields.iter() {
+                let key = Key::Text(name.clone());
+                if let Ok(child) = node.get(&key) {
+                    path.push(key);
+                    do_select(child, next, path, acc);
+                    path.pop();
+                }
+            }
+        }
+        Selector::ExploreIndex { index, next } if node.to_kind() == Kind::List => {
+            let key = Key::Offset(*index);
+            if let Ok(child) = node.get(&key) {
+                path.push(key);
+                do_select(child, next, path, acc);
+                path.pop();
+            }
+        }
+        Selector::ExploreFields { .. } | Selector::ExploreIndex { .. } => (),
+        Selector::ExploreRecursive { limit } => {
+            acc.push((path.clone(), node));
+            if *limit > 0 {
+                let next = Selector::ExploreRecursive { limit: *limit - 1 };
+                for (key, child) in node.iter_entries() {
+                    path.push(key);
+                    do_select(child, &next, path, acc);
+                    path.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "selector_test.rs"]
+mod selector_test;
@@ -0,0 +1,105 @@
+use super::*;
+use crate::ipld::kind::Basic;
+
+fn make_doc() -> Basic {
+    // {"a": 1, "b": [10, 20, {"c": true}], "d": {"e": {"f": null}}}
+    let list: Basic = vec![
+        Basic::Integer(10),
+        Basic::Integer(20),
+        vec![(Key::Text("c".to_string()), Basic::Bool(true))]
+            .into_iter()
+            .collect(),
+    ]
+    .into_iter()
+    .collect();
+    let f: Basic = vec![(Key::Text("f".to_string()), Basic::Null)]
+        .into_iter()
+        .collect();
+    let e: Basic = vec![(Key::Text("e".to_string()), f)].into_iter().collect();
+    vec![
+        (Key::Text("a".to_string()), Basic::Integer(1)),
+        (Key::Text("b".to_string()), list),
+        (Key::Text("d".to_string()), e),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn to_paths(items: &[(Vec<Key>, &dyn Node)]) -> Vec<String> {
+    items
+        .iter()
+        .map(|(path, _)| {
+            let path: Vec<String> = path.iter().map(|k| k.to_string()).collect();
+            path.join("/")
+        })
+        .collect()
+}
+
+#[test]
+fn test_select_fields() {
+    let doc = make_doc();
+    let sel = Selector::ExploreFields {
+        fields: vec![
+            ("a".to_string(), Selector::Matcher),
+            ("d".to_string(), Selector::Matcher),
+            ("missing".to_string(), Selector::Matcher),
+        ],
+    };
+    let items = select(&doc, &sel);
+    assert_eq!(to_paths(&items), vec!["key-str-a", "key-str-d"]);
+    assert_eq!(items[0].1.to_integer(), Some(1));
+    assert_eq!(items[1].1.to_kind(), Kind::Map);
+
+    let sel = Selector::ExploreFields {
+        fields: vec![(
+            "b".to_string(),
+            Selector::ExploreIndex {
+                index: 1,
+                next: Box::new(Selector::Matcher),
+            },
+        )],
+    };
+    let items = select(&doc, &sel);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].1.to_integer(), Some(20));
+
+    // index on a map selects nothing.
+    let sel = Selector::ExploreIndex {
+        index: 0,
+        next: Box::new(Selector::Matcher),
+    };
+    assert!(select(&doc, &sel).is_empty());
+}
+
+#[test]
+fn test_select_all() {
+    let doc = make_doc();
+    let sel = Selector::ExploreAll {
+        next: Box::new(Selector::Matcher),
+    };
+    let items = select(&doc, &sel);
+    assert_eq!(
+        to_paths(&items),
+        vec!["key-str-a", "key-str-b", "key-str-d"]
+    );
+}
+
+#[test]
+fn test_select_recursive() {
+    let doc = make_doc();
+
+    let sel = Selector::ExploreRecursive { limit: 0 };
+    assert_eq!(select(&doc, &sel).len(), 1);
+
+    let sel = Selector::ExploreRecursive { limit: 1 };
+    assert_eq!(select(&doc, &sel).len(), 4);
+
+    let sel = Selector::ExploreFields {
+        fields: vec![("d".to_string(), Selector::ExploreRecursive { limit: 1 })],
+    };
+    let items = select(&doc, &sel);
+    assert_eq!(to_paths(&items), vec!["key-str-d", "key-str-d/key-str-e"]);
+
+    let sel = Selector::ExploreRecursive { limit: 10 };
+    assert_eq!(select(&doc, &sel).len(), 10);
+}
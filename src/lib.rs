@@ -9,16 +9,37 @@
 //! * Between production ready and research friendly, choose research.
 //! * Between performance and composability, choose composable.
 //! * Between rustdoc and unit-test, choose rustdoc first.
+//!
+//! **Features**
+//!
+//! * `std`, enabled by default. Disable default features for `no_std`
+//!   targets, with `alloc`, where only the [ipld] data-model along with
+//!   [cid], [multihash], [multibase] and [multicodec] are available, and
+//!   without the `std::io` based encoders and decoders. The `nostd/`
+//!   crate, in this repository, checks this build.
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![feature(box_syntax, box_patterns)]
 #![feature(trivial_bounds)]
 #![allow(trivial_bounds)]
 #![feature(test)]
 
+#[cfg(feature = "std")]
+extern crate alloc;
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
 extern crate test;
 
-use std::{error, fmt, result};
+use core::{fmt, result};
+#[cfg(feature = "std")]
+use std::error;
+
+#[cfg(not(feature = "std"))]
+use crate::no_std_prelude::*;
 
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate data_encoding_macro;
 
@@ -28,84 +49,178 @@ pub mod cid;
 pub mod multibase;
 pub mod multicodec;
 // mod multiformat;
+#[cfg(feature = "std")]
 pub mod addr_info;
+#[cfg(feature = "std")]
 pub mod multistream;
+#[cfg(feature = "std")]
 pub mod net_addr;
+#[cfg(feature = "std")]
 pub mod net_conn;
+#[cfg(feature = "std")]
 pub mod pb;
+#[cfg(feature = "std")]
 pub mod peer_id;
+#[cfg(feature = "std")]
 pub mod peer_record;
+pub mod prelude;
 
 // modules that have its own sub-directories
+#[cfg(feature = "std")]
 pub mod identity;
+#[cfg(feature = "std")]
 pub mod ipfsd;
 pub mod ipld;
+#[cfg(feature = "std")]
 pub mod multiaddr;
 pub mod multihash;
 
+// items from std's prelude that are not in core's prelude, imported by
+// modules that are built for no_std targets.
+#[cfg(not(feature = "std"))]
+mod no_std_prelude {
+    pub use alloc::{
+        borrow::ToOwned,
+        boxed::Box,
+        string::{String, ToString},
+        vec::Vec,
+    };
+}
+
 /// Type alias for Result return type, used by this package.
 pub type Result<T> = result::Result<T, Error>;
 
+/// Type alias for the underlying cause of an [Error].
+#[cfg(feature = "std")]
+pub type Source = Box<dyn error::Error + Send + Sync + 'static>;
+
+/// Type alias for the underlying cause of an [Error]. Without `std`
+/// there is no `Error` trait, the cause is only displayed.
+#[cfg(not(feature = "std"))]
+pub type Source = Box<dyn fmt::Display + Send + Sync + 'static>;
+
 /// Error variants that can be returned by this package's API.
 ///
 /// Each variant carries a prefix, typically identifying the
-/// error location.
+/// error location, a message, and optionally the underlying error
+/// that caused it. Errors created using `err_at!` macro carry the
+/// `file:line` of their origin as prefix, and are displayed as
+/// `<file:line> <kind>: <message>`.
 pub enum Error {
-    Fatal(String, String),
-    FailConvert(String, String),
-    IOError(String, String),
-    SysFail(String, String),
-    IPCFail(String, String),
-    IndexFail(String, String),
-    FailCbor(String, String),
-    ThreadFail(String, String),
-    FilePath(String, String),
-    Invalid(String, String),
-    ParseError(String, String),
-    DecodeError(String, String),
-    EncodeError(String, String),
-    DnsError(String, String),
-    SigningError(String, String),
-    BadInput(String, String),
-    BadCodec(String, String),
-    BadAddr(String, String),
-    HashFail(String, String),
-    NotImplemented(String, String),
+    Fatal(String, String, Option<Source>),
+    FailConvert(String, String, Option<Source>),
+    IOError(String, String, Option<Source>),
+    SysFail(String, String, Option<Source>),
+    IPCFail(String, String, Option<Source>),
+    IndexFail(String, String, Option<Source>),
+    FailCbor(String, String, Option<Source>),
+    ThreadFail(String, String, Option<Source>),
+    FilePath(String, String, Option<Source>),
+    Invalid(String, String, Option<Source>),
+    ParseError(String, String, Option<Source>),
+    DecodeError(String, String, Option<Source>),
+    EncodeError(String, String, Option<Source>),
+    DnsError(String, String, Option<Source>),
+    SigningError(String, String, Option<Source>),
+    BadInput(String, String, Option<Source>),
+    BadCodec(String, String, Option<Source>),
+    BadAddr(String, String, Option<Source>),
+    HashFail(String, String, Option<Source>),
+    NotImplemented(String, String, Option<Source>),
+    DepthExceeded(String, String, Option<Source>),
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+/// Machine readable kind of [Error], one for each variant. Use this to
+/// branch on errors programmatically, instead of matching on messages.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    Fatal,
+    FailConvert,
+    IOError,
+    SysFail,
+    IPCFail,
+    IndexFail,
+    FailCbor,
+    ThreadFail,
+    FilePath,
+    Invalid,
+    ParseError,
+    DecodeError,
+    EncodeError,
+    DnsError,
+    SigningError,
+    BadInput,
+    BadCodec,
+    BadAddr,
+    HashFail,
+    NotImplemented,
+    DepthExceeded,
+}
+
+impl Error {
+    /// Return the kind of error.
+    pub fn kind(&self) -> ErrorKind {
+        self.as_parts().0
+    }
+
+    /// Return the origin of error, as `file:line`, where it was created.
+    pub fn location(&self) -> &str {
+        self.as_parts().1
+    }
+
+    /// Return the error message, without location and kind.
+    pub fn message(&self) -> &str {
+        self.as_parts().2
+    }
+
+    fn as_parts(&self) -> (ErrorKind, &str, &str, &Option<Source>) {
         use Error::*;
 
         match self {
-            Fatal(p, msg) => write!(f, "{} Fatal: {}", p, msg),
-            FailConvert(p, msg) => write!(f, "{} FailConvert: {}", p, msg),
-            IOError(p, msg) => write!(f, "{} IOError: {}", p, msg),
-            SysFail(p, msg) => write!(f, "{} SysFail: {}", p, msg),
-            IPCFail(p, msg) => write!(f, "{} IPCFail: {}", p, msg),
-            IndexFail(p, msg) => write!(f, "{} IndexFail: {}", p, msg),
-            FailCbor(p, msg) => write!(f, "{} FailCbor: {}", p, msg),
-            ThreadFail(p, msg) => write!(f, "{} ThreadFail: {}", p, msg),
-            FilePath(p, msg) => write!(f, "{} FilePath: {}", p, msg),
-            Invalid(p, msg) => write!(f, "{} Invalid: {}", p, msg),
-            ParseError(p, msg) => write!(f, "{} ParseError: {}", p, msg),
-            DecodeError(p, msg) => write!(f, "{} DecodeError: {}", p, msg),
-            EncodeError(p, msg) => write!(f, "{} EncodeError: {}", p, msg),
-            DnsError(p, msg) => write!(f, "{} DnsError: {}", p, msg),
-            SigningError(p, msg) => write!(f, "{} SigningError: {}", p, msg),
-            BadInput(p, msg) => write!(f, "{} BadInput: {}", p, msg),
-            BadCodec(p, msg) => write!(f, "{} BadCodec: {}", p, msg),
-            BadAddr(p, msg) => write!(f, "{} BadAddr: {}", p, msg),
-            HashFail(p, msg) => write!(f, "{} HashFail: {}", p, msg),
-            NotImplemented(p, msg) => write!(f, "{} NotImplemented: {}", p, msg),
+            Fatal(p, msg, src) => (ErrorKind::Fatal, p, msg, src),
+            FailConvert(p, msg, src) => (ErrorKind::FailConvert, p, msg, src),
+            IOError(p, msg, src) => (ErrorKind::IOError, p, msg, src),
+            SysFail(p, msg, src) => (ErrorKind::SysFail, p, msg, src),
+            IPCFail(p, msg, src) => (ErrorKind::IPCFail, p, msg, src),
+            IndexFail(p, msg, src) => (ErrorKind::IndexFail, p, msg, src),
+            FailCbor(p, msg, src) => (ErrorKind::FailCbor, p, msg, src),
+            ThreadFail(p, msg, src) => (ErrorKind::ThreadFail, p, msg, src),
+            FilePath(p, msg, src) => (ErrorKind::FilePath, p, msg, src),
+            Invalid(p, msg, src) => (ErrorKind::Invalid, p, msg, src),
+            ParseError(p, msg, src) => (ErrorKind::ParseError, p, msg, src),
+            DecodeError(p, msg, src) => (ErrorKind::DecodeError, p, msg, src),
+            EncodeError(p, msg, src) => (ErrorKind::EncodeError, p, msg, src),
+            DnsError(p, msg, src) => (ErrorKind::DnsError, p, msg, src),
+            SigningError(p, msg, src) => (ErrorKind::SigningError, p, msg, src),
+            BadInput(p, msg, src) => (ErrorKind::BadInput, p, msg, src),
+            BadCodec(p, msg, src) => (ErrorKind::BadCodec, p, msg, src),
+            BadAddr(p, msg, src) => (ErrorKind::BadAddr, p, msg, src),
+            HashFail(p, msg, src) => (ErrorKind::HashFail, p, msg, src),
+            NotImplemented(p, msg, src) => (ErrorKind::NotImplemented, p, msg, src),
+            DepthExceeded(p, msg, src) => (ErrorKind::DepthExceeded, p, msg, src),
         }
     }
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        let (kind, p, msg, _) = self.as_parts();
+        write!(f, "{} {:?}: {}", p, kind, msg)
+    }
+}
+
 impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
         write!(f, "{}", self)
     }
 }
 
-impl error::Error for Error {}
+#[cfg(feature = "std")]
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self.as_parts() {
+            (_, _, _, Some(src)) => Some(&**src),
+            (_, _, _, None) => None,
+        }
+    }
+}
@@ -4,6 +4,8 @@
 
 use multibase;
 
+#[cfg(not(feature = "std"))]
+use crate::no_std_prelude::*;
 use crate::{Error, Result};
 
 /// Type to encode/decode bytes into/from multi-base formats.
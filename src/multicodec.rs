@@ -7,10 +7,13 @@
 //! [multicodec]: https://github.com/multiformats/multicodec
 //! [TABLE]: https://github.com/multiformats/multicodec/blob/master/table.csv
 
+#[cfg(feature = "std")]
 use lazy_static::lazy_static;
 
-use std::{fmt, result};
+use core::{fmt, result};
 
+#[cfg(not(feature = "std"))]
+use crate::no_std_prelude::*;
 use crate::{Error, Result};
 
 /// Type implements [multicodec] and [unsigned-varint] specs.
@@ -109,6 +112,23 @@ macro_rules! code_points {
             }
         }
 
+        /// Return the code-value for codec `name`, as in the default [TABLE].
+        pub fn code_of(name: &str) -> Option<u128> {
+            match name {
+                $( $name => Some($code), )*
+                _ => None,
+            }
+        }
+
+        /// Return the name of codec for `code`, as in the default [TABLE].
+        pub fn name_of(code: u128) -> Option<&'static str> {
+            match code {
+                $( $code => Some($name), )*
+                _ => None,
+            }
+        }
+
+        #[cfg(feature = "std")]
         lazy_static! {
             /// Default codec table. Refer [table] for details.
             ///
@@ -1113,6 +1133,7 @@ code_points![
 ];
 
 /// Return a list of code-points tagged as "multihash".
+#[cfg(feature = "std")]
 pub fn multihash_codes() -> Vec<u128> {
     TABLE_MULTIHASH
         .clone()
@@ -72,3 +72,26 @@ fn test_codec() {
         assert_eq!(res_buf, vec![].as_slice(), "{:?}", code);
     }
 }
+
+#[test]
+fn test_code_of_name_of() {
+    let refs = vec![
+        ("dag-cbor", DAG_CBOR),
+        ("dag-pb", DAG_PB),
+        ("raw", RAW),
+        ("dag-json", DAG_JSON),
+        ("sha2-256", SHA2_256),
+        ("blake3", BLAKE3),
+        ("identity", IDENTITY),
+    ];
+    for (name, code) in refs.into_iter() {
+        assert_eq!(code_of(name), Some(code), "{}", name);
+        assert_eq!(name_of(code), Some(name), "{}", name);
+        assert_eq!(name_of(code_of(name).unwrap()), Some(name));
+    }
+    assert_eq!(DAG_CBOR, 0x71);
+    assert_eq!(RAW, 0x55);
+
+    assert_eq!(code_of("no-such-codec"), None);
+    assert_eq!(name_of(0xdead_beef_u128), None);
+}
@@ -1,3 +1,5 @@
+#[cfg(not(feature = "std"))]
+use crate::no_std_prelude::*;
 use crate::{multicodec, Error, Result};
 
 #[derive(Clone)]
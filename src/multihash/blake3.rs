@@ -1,8 +1,14 @@
+#[cfg(not(feature = "std"))]
+use crate::no_std_prelude::*;
 use crate::{Error, Result};
 
+/// Default digest length for blake3, in bytes.
+pub(crate) const BLAKE3_LEN: usize = 32;
+
 #[derive(Clone)]
 pub(crate) struct Blake3 {
     hasher: blake3::Hasher,
+    len: usize,
     digest: Option<Vec<u8>>,
 }
 
@@ -15,9 +21,19 @@ impl PartialEq for Blake3 {
 }
 
 impl Blake3 {
-    pub(crate) fn from_code(_code: u128) -> Result<Blake3> {
+    pub(crate) fn from_code(code: u128) -> Result<Blake3> {
+        Self::with_len(code, BLAKE3_LEN)
+    }
+
+    /// blake3 is an extendable output function, digest can be of any
+    /// length `len`, in bytes.
+    pub(crate) fn with_len(_code: u128, len: usize) -> Result<Blake3> {
+        if len == 0 {
+            err_at!(Invalid, msg: "blake3 digest of zero length")?
+        }
         Ok(Blake3 {
             hasher: blake3::Hasher::new(),
+            len,
             digest: None,
         })
     }
@@ -25,6 +41,7 @@ impl Blake3 {
     pub(crate) fn decode(_code: u128, digest: &[u8]) -> Result<Blake3> {
         Ok(Blake3 {
             hasher: blake3::Hasher::new(),
+            len: digest.len(),
             digest: Some(digest.to_vec()),
         })
     }
@@ -40,8 +57,9 @@ impl Blake3 {
     pub(crate) fn finish(&mut self) -> Result<()> {
         self.digest = match &self.digest {
             None => {
-                let hash = blake3::Hasher::finalize(&self.hasher);
-                Some(hash.as_bytes().to_vec())
+                let mut digest = vec![0; self.len];
+                self.hasher.finalize_xof().fill(&mut digest);
+                Some(digest)
             }
             Some(_) => err_at!(Invalid, msg: "double finalize")?,
         };
@@ -17,13 +17,17 @@ mod sha2;
 mod sha3;
 mod skein;
 
-use std::{fmt, io, result};
+use core::{fmt, result};
+#[cfg(feature = "std")]
+use std::io;
 
 use crate::multihash::{
     blake2b::Blake2b, blake2s::Blake2s, blake3::Blake3, identity::Identity, md4::Md4, md5::Md5,
     ripemd::RipeMd, sha1::Sha1, sha2::Sha2, sha3::Sha3, skein::Skein,
 };
 
+#[cfg(not(feature = "std"))]
+use crate::no_std_prelude::*;
 use crate::{multicodec, multicodec::Multicodec, Error, Result};
 
 /// Type adapts several hashing algorithms within [multihash] specification.
@@ -52,8 +56,8 @@ enum Inner {
 
 impl fmt::Display for Multihash {
     fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        use core::iter::FromIterator;
         use multibase::Base::Base16Lower;
-        use std::iter::FromIterator;
         use Inner::*;
 
         fn get_parts(inner: &Inner) -> Option<(Multicodec, Vec<u8>)> {
@@ -164,6 +168,18 @@ impl Multihash {
         Ok(mh)
     }
 
+    /// Create a blake3 Multihash for `data`, with a digest of `len` bytes.
+    /// Blake3 has extendable output, [Multihash::new] creates the default
+    /// 32-byte digest, use this for other lengths.
+    pub fn new_blake3(data: &[u8], len: usize) -> Result<Multihash> {
+        let codec: Multicodec = multicodec::BLAKE3.into();
+        let hasher = Blake3::with_len(codec.to_code(), len)?;
+        let mut mh: Multihash = Inner::Blake3(codec, hasher).into();
+        mh.write(data)?.finish()?;
+
+        Ok(mh)
+    }
+
     /// New multihash from digest and multihash-type.
     pub fn from_digest(codec: Multicodec, digest: &[u8]) -> Result<Multihash> {
         let code = codec.to_code();
@@ -287,10 +303,7 @@ impl Multihash {
 
     // Similar to encode() but avoid allocation by using supplied buffer
     // `buf`.
-    fn encode_with<W>(&self, buf: &mut W) -> Result<usize>
-    where
-        W: io::Write,
-    {
+    fn encode_with(&self, buf: &mut Vec<u8>) -> Result<usize> {
         use unsigned_varint::encode;
 
         let digest = match &self.inner {
@@ -309,7 +322,7 @@ impl Multihash {
         };
         let n = {
             let out = self.to_codec()?.encode()?;
-            err_at!(IOError, buf.write(&out))?;
+            buf.extend_from_slice(&out);
             out.len()
         };
         let m = {
@@ -319,10 +332,10 @@ impl Multihash {
             let mut scratch: [u8; 5] = Default::default();
 
             let slice = encode::usize(digest.len(), &mut scratch);
-            err_at!(IOError, buf.write(slice))?;
+            buf.extend_from_slice(slice);
             slice.len()
         };
-        err_at!(IOError, buf.write(digest))?;
+        buf.extend_from_slice(digest);
         Ok(n + m + digest.len())
     }
 
@@ -438,6 +451,30 @@ impl Multihash {
         }
     }
 
+    // codec and digest, without allocating, used for comparing and hashing
+    // CIDs. Return `None` if digest is not generated or is malformed.
+    pub(crate) fn as_parts(&self) -> Option<(u128, &[u8])> {
+        let (codec, digest) = match &self.inner {
+            Inner::Identity(c, h) => (c, h.as_digest().ok()?),
+            Inner::Sha1(c, h) => (c, h.as_digest().ok()?),
+            Inner::Sha2(c, h) => (c, h.as_digest().ok()?),
+            Inner::Sha3(c, h) => (c, h.as_digest().ok()?),
+            Inner::Blake3(c, h) => (c, h.as_digest().ok()?),
+            Inner::Blake2b(c, h) => (c, h.as_digest().ok()?),
+            Inner::Blake2s(c, h) => (c, h.as_digest().ok()?),
+            Inner::Md4(c, h) => (c, h.as_digest().ok()?),
+            Inner::Md5(c, h) => (c, h.as_digest().ok()?),
+            Inner::Skein(c, h) => (c, h.as_digest().ok()?),
+            Inner::RipeMd(c, h) => (c, h.as_digest().ok()?),
+            Inner::Binary(data) => {
+                let (codec, rem) = Multicodec::decode(data).ok()?;
+                let (n, rem) = unsigned_varint::decode::usize(rem).ok()?;
+                return Some((codec.to_code(), rem.get(..n)?));
+            }
+        };
+        Some((codec.to_code(), digest))
+    }
+
     /// Unwrap the underlying codec and hash digest. Panic if digest
     /// is not generated or decoded.
     pub fn unwrap(self) -> Result<(Multicodec, Vec<u8>)> {
@@ -458,6 +495,7 @@ impl Multihash {
     }
 }
 
+#[cfg(feature = "std")]
 impl io::Write for Multihash {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         self.write(buf)
@@ -69,3 +69,48 @@ fn test_multihash_pretty() {
         "sha2-256-256-b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9".to_string(),
     );
 }
+
+#[test]
+fn test_shake() {
+    use crate::multibase;
+
+    let testcases = [
+        (
+            multicodec::SHAKE_128,
+            "",
+            "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26",
+        ),
+        (
+            multicodec::SHAKE_128,
+            "Hello world",
+            "c1301df86b1dc67ce3b5a067dc9b47affca8caa08f41d1efa614cea56f526897",
+        ),
+        (
+            multicodec::SHAKE_256,
+            "",
+            concat!(
+                "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f",
+                "d75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be"
+            ),
+        ),
+        (
+            multicodec::SHAKE_256,
+            "Hello world",
+            concat!(
+                "20740b4c7a7997765e9cc254b44a1589e60849be0fe70b68a6fb732415edaa13",
+                "3bb6eb7825ffa531d84ef94c2a8309c2a342b05356f110c2d46e90c928e3ecd6"
+            ),
+        ),
+    ];
+
+    for (codec, data, digest) in testcases.iter() {
+        let mh = Multihash::new((*codec).into(), data.as_bytes()).unwrap();
+        let out = mh.to_digest().unwrap();
+        let mb = multibase::Multibase::with_base(Base::Base16Lower, &out).unwrap();
+        assert_eq!(mb.to_text().unwrap(), format!("f{}", digest), "{:?}", data);
+
+        let (mh2, rem) = Multihash::decode(&mh.encode().unwrap()).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(mh2.to_digest().unwrap(), out);
+    }
+}
@@ -1,5 +1,7 @@
 use digest::Digest;
 
+#[cfg(not(feature = "std"))]
+use crate::no_std_prelude::*;
 use crate::{multicodec, Error, Result};
 
 #[derive(Clone)]
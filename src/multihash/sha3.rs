@@ -1,7 +1,7 @@
-use digest::Digest;
-
-use std::io::Read;
+use digest::{Digest, XofReader};
 
+#[cfg(not(feature = "std"))]
+use crate::no_std_prelude::*;
 use crate::{multicodec, Error, Result};
 
 #[derive(Clone)]
@@ -265,18 +265,18 @@ impl Sha3 {
                 hasher,
                 digest: digest @ None,
             } => {
-                let mut buf = Vec::default();
-                let mut xof = hasher.finalize_xof_reset();
-                err_at!(IOError, xof.read_to_end(&mut buf))?;
+                // extendable output, digest is 256-bits as in go-multihash.
+                let mut buf = vec![0; 32];
+                hasher.finalize_xof_reset().read(&mut buf);
                 *digest = Some(buf);
             }
             Sha3::Shake256 {
                 hasher,
                 digest: digest @ None,
             } => {
-                let mut buf = Vec::default();
-                let mut xof = hasher.finalize_xof_reset();
-                err_at!(IOError, xof.read_to_end(&mut buf))?;
+                // extendable output, digest is 512-bits as in go-multihash.
+                let mut buf = vec![0; 64];
+                hasher.finalize_xof_reset().read(&mut buf);
                 *digest = Some(buf)
             }
             Sha3::Keccak224 {
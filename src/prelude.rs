@@ -0,0 +1,13 @@
+//! Module re-export commonly used traits and types, so that they can be
+//! imported in one go, `use iprs::prelude::*`. [crate::Result] is left
+//! out, so that the glob import does not shadow std's `Result`.
+
+pub use crate::{
+    cid::Cid,
+    ipld::kind::{Basic, Key, Kind, Node, ToKey},
+    Error, ErrorKind,
+};
+
+#[cfg(test)]
+#[path = "prelude_test.rs"]
+mod prelude_test;
@@ -0,0 +1,30 @@
+use super::*;
+
+#[test]
+fn test_prelude() {
+    let doc: Basic = vec![
+        (Key::Text("a".to_string()), Basic::from(10)),
+        (
+            Key::Text("b".to_string()),
+            vec![Basic::from("x"), Basic::from(true)]
+                .into_iter()
+                .collect(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(doc.to_kind(), Kind::Map);
+    assert_eq!(doc.get_key("a").unwrap().to_integer(), Some(10));
+    let list = doc.get_key("b").unwrap();
+    assert_eq!(list.get_key(1).unwrap().to_bool(), Some(true));
+
+    let err: Error = doc.get_key("c").err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::IndexFail);
+
+    // std's Result is not shadowed by the glob import.
+    let res: Result<Cid, Error> = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n".parse();
+    assert!(res.is_ok());
+    let res: Result<u8, String> = Err("not iprs".to_string());
+    assert!(res.is_err());
+}
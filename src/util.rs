@@ -1,13 +1,19 @@
 //! Module implement useful functions.
+//!
+//! Except for [err_at], functions in this module need `std`.
 
+#[cfg(feature = "std")]
 use crossbeam_channel as cbm;
+#[cfg(feature = "std")]
 use rand::{
     rngs::{SmallRng, StdRng},
     SeedableRng,
 };
 
+#[cfg(feature = "std")]
 use std::{convert::TryInto, ffi, io, path, time};
 
+#[cfg(feature = "std")]
 use crate::{Error, Result};
 
 /// Short form to compose Error values.
@@ -16,7 +22,7 @@ use crate::{Error, Result};
 ///
 /// ```ignore
 /// use crate::Error;
-/// err_at!(Error::Invalid(String::default(), "bad argument"));
+/// err_at!(Error::Invalid(String::default(), "bad argument", None));
 /// ```
 ///
 /// ```ignore
@@ -34,17 +40,58 @@ use crate::{Error, Result};
 /// err_at!(Invalid, std::fs::read(file_path), format!("read failed"));
 /// ```
 ///
+/// To preserve the underlying error as the [std::error::Error::source],
+/// error value must be `std::error::Error + Send + Sync + 'static`:
+///
+/// ```ignore
+/// use crate::Error;
+/// err_at!(FailConvert, source: std::str::from_utf8(buf));
+/// err_at!(FailConvert, source: std::str::from_utf8(buf), "at {}", off);
+/// ```
+///
 #[macro_export]
 macro_rules! err_at {
     ($v:ident, msg: $($arg:expr),+) => {{
         use log::error;
 
         let prefix = format!("{}:{}", file!(), line!());
-        let err = Error::$v(prefix, format!($($arg),+));
+        let err = Error::$v(prefix, format!($($arg),+), None);
 
         error!("{}", err);
         Err(err)
     }};
+    ($v:ident, source: $e:expr) => {{
+        use log::error;
+
+        match $e {
+            Ok(val) => Ok(val),
+            Err(err) => {
+                let prefix = format!("{}:{}", file!(), line!());
+                let msg = format!("{}", err);
+                let source: $crate::Source = Box::new(err);
+                let err = Error::$v(prefix, msg, Some(source));
+
+                error!("{}", err);
+                Err(err)
+            }
+        }
+    }};
+    ($v:ident, source: $e:expr, $($arg:expr),+) => {{
+        use log::error;
+
+        match $e {
+            Ok(val) => Ok(val),
+            Err(err) => {
+                let prefix = format!("{}:{}", file!(), line!());
+                let msg = format!("{} {}", err, format!($($arg),+));
+                let source: $crate::Source = Box::new(err);
+                let err = Error::$v(prefix, msg, Some(source));
+
+                error!("{}", err);
+                Err(err)
+            }
+        }
+    }};
     ($v:ident, $e:expr) => {{
         use log::error;
 
@@ -52,7 +99,7 @@ macro_rules! err_at {
             Ok(val) => Ok(val),
             Err(err) => {
                 let prefix = format!("{}:{}", file!(), line!());
-                let err = Error::$v(prefix, format!("{}", err));
+                let err = Error::$v(prefix, format!("{}", err), None);
 
                 error!("{}", err);
                 Err(err)
@@ -67,7 +114,7 @@ macro_rules! err_at {
             Err(err) => {
                 let prefix = format!("{}:{}", file!(), line!());
                 let msg = format!($($arg),+);
-                let err = Error::$v(prefix, format!("{} {}", err, msg));
+                let err = Error::$v(prefix, format!("{} {}", err, msg), None);
 
                 error!("{}", err);
 
@@ -77,6 +124,7 @@ macro_rules! err_at {
     }};
 }
 
+#[cfg(feature = "std")]
 pub fn ctrl_channel() -> Result<cbm::Receiver<time::Instant>> {
     let (sender, receiver) = cbm::bounded(100);
     err_at!(
@@ -91,6 +139,7 @@ pub fn ctrl_channel() -> Result<cbm::Receiver<time::Instant>> {
 
 /// Convert relative path, and ~ path into absolute path. Note that
 /// the supplied path must exist.
+#[cfg(feature = "std")]
 pub fn canonicalize(loc: ffi::OsString) -> Result<ffi::OsString> {
     use std::iter::FromIterator;
 
@@ -119,6 +168,7 @@ pub fn canonicalize(loc: ffi::OsString) -> Result<ffi::OsString> {
 }
 
 /// Create a new insecure but fast psuedo-random-number-generator.
+#[cfg(feature = "std")]
 pub fn new_prng(seed: Option<u128>) -> Result<SmallRng> {
     let rng = match seed {
         Some(seed) => SmallRng::from_seed(seed.to_be_bytes()),
@@ -129,6 +179,7 @@ pub fn new_prng(seed: Option<u128>) -> Result<SmallRng> {
 }
 
 /// Create a new cryptographically secure psuedo-random-number-generator.
+#[cfg(feature = "std")]
 pub fn new_csprng(seed: Option<u128>) -> Result<StdRng> {
     let seed = seed.unwrap_or(
         err_at!(
@@ -148,6 +199,7 @@ pub fn new_csprng(seed: Option<u128>) -> Result<StdRng> {
 }
 
 /// Check whether _ENV_VAR_ `name` is set to "true" or "t" or "1"
+#[cfg(feature = "std")]
 pub fn get_env_bool(name: String) -> bool {
     use std::env;
 
@@ -158,12 +210,14 @@ pub fn get_env_bool(name: String) -> bool {
 }
 
 /// XOR two slice and return the new slice.
+#[cfg(feature = "std")]
 #[inline]
 pub fn xor_slice(a: &[u8], b: &[u8]) -> Vec<u8> {
     a.iter().zip(b.iter()).map(|(a, b)| a ^ b).collect()
 }
 
 /// Read length-prefixed-message.
+#[cfg(feature = "std")]
 pub fn read_lpm<R: io::Read>(r: &mut R) -> Result<Vec<u8>> {
     use unsigned_varint::decode as uvd;
 
@@ -188,6 +242,7 @@ pub fn read_lpm<R: io::Read>(r: &mut R) -> Result<Vec<u8>> {
 }
 
 /// Write data as length-prefixed-message.
+#[cfg(feature = "std")]
 pub fn write_lpm<W: io::Write>(w: &mut W, data: &[u8]) -> Result<usize> {
     use unsigned_varint::encode as uve;
 
@@ -199,6 +254,7 @@ pub fn write_lpm<W: io::Write>(w: &mut W, data: &[u8]) -> Result<usize> {
 }
 
 /// Write data as length-prefixed-message and flush the writer.
+#[cfg(feature = "std")]
 pub fn flush_lpm<W: io::Write>(w: &mut W, data: &[u8]) -> Result<usize> {
     let n = match data.len() {
         0 => 0,